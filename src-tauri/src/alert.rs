@@ -3,7 +3,7 @@ use log::error;
 use serde_json::json;
 use tauri::{AppHandle, Manager, Runtime};
 
-use crate::SettingsManagerState;
+use crate::{cli, notification, SettingsManagerState};
 #[cfg(target_os = "macos")]
 use tauri::ActivationPolicy;
 use tauri_plugin_aptabase::EventTracker;
@@ -120,6 +120,10 @@ fn display_alert<R>(app: &AppHandle<R>, title: &str, message: &str) -> Result<()
 where
     R: Runtime,
 {
+    if cli::is_headless(app) {
+        return notification::show_message(title, message);
+    }
+
     #[cfg(target_os = "macos")]
     app.app_handle()
         .set_activation_policy(ActivationPolicy::Regular)?;
@@ -0,0 +1,98 @@
+use crate::instance_control::ControlCommand;
+use crate::settings_overrides::SettingsOverrides;
+use crate::CliState;
+use clap::Parser;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Just Drink! reminds you to drink enough water during the day.
+///
+/// Settings given on the command line apply to this session only and are never saved.
+/// Commands are sent to the running instance.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "justdrink", version)]
+pub struct Cli {
+    /// Start in the background without showing the dashboard
+    #[arg(long)]
+    pub quiet: bool,
+
+    /// Run without any window, reminders appear as desktop notifications
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Minutes between two drink reminders
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u32).range(1..=720))]
+    pub interval: Option<u32>,
+
+    /// Keep the reminder running while you are away or in a call
+    #[arg(long)]
+    pub no_idle_detection: bool,
+
+    /// Use the settings of the named profile
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Read and write the settings in this directory
+    #[arg(long, value_name = "DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    /// One of off, error, warn, info, debug or trace
+    #[arg(long, value_name = "LEVEL", default_value_t = log::LevelFilter::Trace)]
+    pub log_level: log::LevelFilter,
+
+    /// File for the answer of the running instance to a forwarded command
+    #[arg(long, value_name = "FILE", hide = true)]
+    pub reply_to: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<ControlCommand>,
+}
+
+impl Cli {
+    /// Parses the arguments of this process. Arguments added by the system, like `-psn_…` on
+    /// macOS or flags of an autostart entry, start the app normally instead of exiting.
+    pub fn parse_or_default() -> Self {
+        Cli::try_parse().unwrap_or_else(|err| {
+            if !err.use_stderr() {
+                // --help and --version
+                err.exit();
+            }
+            eprintln!("{}ignore the arguments and start normally", err);
+            Cli::parse_from(std::env::args_os().take(1))
+        })
+    }
+
+    pub fn settings_overrides(&self) -> SettingsOverrides {
+        SettingsOverrides {
+            next_break_duration_minutes: self.interval,
+            disable_idle_detection: self.no_idle_detection,
+            profile: self.profile.clone(),
+        }
+    }
+}
+
+/// True if started with `--headless`, no window must be opened then.
+pub fn is_headless<R: Runtime>(app: &AppHandle<R>) -> bool {
+    app.try_state::<CliState>().is_some_and(|cli| cli.headless)
+}
+
+/// Directory of the settings files, `--config-dir` or the app data directory.
+pub fn config_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, anyhow::Error> {
+    let config_dir = app
+        .try_state::<CliState>()
+        .and_then(|cli| cli.config_dir.clone());
+    let dir = match config_dir {
+        Some(dir) => dir,
+        None => app.path().app_data_dir()?,
+    };
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+pub fn config_path<R: Runtime>(
+    app: &AppHandle<R>,
+    file_name: &str,
+) -> Result<PathBuf, anyhow::Error> {
+    Ok(config_dir(app)?.join(file_name))
+}
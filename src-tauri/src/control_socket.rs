@@ -0,0 +1,313 @@
+use crate::countdown_timer::{ChangeTime, CountdownEvent};
+use crate::instance_control::{self, ControlCommand};
+use crate::model::event::IntakeEvent;
+use crate::CountdownTimerState;
+use anyhow::anyhow;
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs::{self, DirBuilder};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use strum::VariantNames;
+use tauri::{AppHandle, Manager};
+use tauri_specta::Event;
+
+const SOCKET_NAME: &str = "control.sock";
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const PARSE_ERROR: i64 = -32700;
+const COMMAND_FAILED: i64 = -32000;
+
+/// Methods of the JSON-RPC 2.0 endpoint, one request per line. The variant names tell an
+/// unknown method from invalid params.
+#[derive(Deserialize, Debug, PartialEq, VariantNames)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+enum RpcMethod {
+    Status,
+    Toggle,
+    ChangeTime(ChangeTime),
+    StartSession,
+    Pause {
+        #[serde(default)]
+        seconds: Option<u64>,
+    },
+    Resume,
+    LogIntake {
+        ml: u32,
+    },
+    Progress,
+    /// Sends every `CountdownEvent` as `countdown` and every `IntakeEvent` as `intake`
+    /// notification on this connection.
+    Subscribe,
+}
+
+#[derive(Debug, PartialEq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+type Connection = Arc<Mutex<UnixStream>>;
+
+/// Per user directory of the socket, only accessible by the user.
+pub fn socket_path() -> PathBuf {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) => PathBuf::from(runtime_dir).join("justdrink"),
+        None => std::env::temp_dir().join(format!(
+            "justdrink-{}",
+            std::env::var("USER").unwrap_or_default()
+        )),
+    };
+    dir.join(SOCKET_NAME)
+}
+
+pub fn init(app: &AppHandle) -> Result<(), anyhow::Error> {
+    let listener = bind()?;
+    let subscribers: Arc<Mutex<Vec<Connection>>> = Arc::new(Mutex::new(Vec::new()));
+
+    let countdown_subscribers = subscribers.clone();
+    CountdownEvent::listen(app, move |event| {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "countdown",
+            "params": event.payload,
+        });
+        let mut subscribers = countdown_subscribers.lock().unwrap();
+        subscribers.retain(|connection| send(connection, &notification).is_ok());
+    });
+    let intake_subscribers = subscribers.clone();
+    IntakeEvent::listen(app, move |event| {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "intake",
+            "params": event.payload,
+        });
+        let mut subscribers = intake_subscribers.lock().unwrap();
+        subscribers.retain(|connection| send(connection, &notification).is_ok());
+    });
+
+    let app_handle = app.clone();
+    thread::Builder::new()
+        .name("control-socket".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let app_handle = app_handle.clone();
+                        let subscribers = subscribers.clone();
+                        thread::spawn(move || serve(&app_handle, stream, &subscribers));
+                    }
+                    Err(err) => warn!("control socket connection failed: {:?}", err),
+                }
+            }
+        })?;
+    Ok(())
+}
+
+fn bind() -> Result<UnixListener, anyhow::Error> {
+    let path = socket_path();
+    let dir = path
+        .parent()
+        .ok_or_else(|| anyhow!("no directory for {:?}", path))?;
+    DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(anyhow!("{:?} is used by another process", path));
+        }
+        fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    info!("control socket listening on {:?}", path);
+    Ok(listener)
+}
+
+fn serve(app: &AppHandle, stream: UnixStream, subscribers: &Mutex<Vec<Connection>>) {
+    let reader = match stream.try_clone() {
+        Ok(reader) => BufReader::new(reader),
+        Err(err) => {
+            error!("unable to read from control socket: {:?}", err);
+            return;
+        }
+    };
+    stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok();
+    let connection: Connection = Arc::new(Mutex::new(stream));
+
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (id, result) = match parse_request(&line) {
+            Ok((id, method)) => {
+                let result = call(app, &method);
+                if method == RpcMethod::Subscribe {
+                    subscribers.lock().unwrap().push(connection.clone());
+                }
+                (id, result)
+            }
+            Err((id, err)) => (id, Err(err)),
+        };
+        // requests without id are notifications and get no response
+        let Some(id) = id else {
+            continue;
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(err) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": err.code, "message": err.message },
+            }),
+        };
+        if send(&connection, &response).is_err() {
+            break;
+        }
+    }
+    debug!("control socket connection closed");
+}
+
+fn parse_request(line: &str) -> Result<(Option<Value>, RpcMethod), (Option<Value>, RpcError)> {
+    let request: Value = serde_json::from_str(line).map_err(|err| {
+        (
+            Some(Value::Null),
+            RpcError {
+                code: PARSE_ERROR,
+                message: err.to_string(),
+            },
+        )
+    })?;
+    let id = request.get("id").cloned();
+    let error = |code, message: String| (id.clone(), RpcError { code, message });
+
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return Err(error(INVALID_REQUEST, "method is missing".to_string()));
+    };
+    if !RpcMethod::VARIANTS.contains(&method) {
+        return Err(error(
+            METHOD_NOT_FOUND,
+            format!("unknown method '{}'", method),
+        ));
+    }
+    let call = json!({
+        "method": method,
+        "params": request.get("params"),
+    });
+    serde_json::from_value::<RpcMethod>(call)
+        .map(|method| (id.clone(), method))
+        .map_err(|err| error(INVALID_PARAMS, err.to_string()))
+}
+
+fn call(app: &AppHandle, method: &RpcMethod) -> Result<Value, RpcError> {
+    let command = match method {
+        RpcMethod::Status | RpcMethod::Subscribe => None,
+        RpcMethod::Toggle => Some(ControlCommand::Toggle),
+        RpcMethod::ChangeTime(change_time) => {
+            app.state::<CountdownTimerState>()
+                .change(change_time.clone());
+            None
+        }
+        RpcMethod::StartSession => Some(ControlCommand::DrinkNow),
+        RpcMethod::Pause { seconds } => Some(ControlCommand::Pause { seconds: *seconds }),
+        RpcMethod::Resume => Some(ControlCommand::Resume),
+        RpcMethod::LogIntake { ml } => Some(ControlCommand::Log { ml: *ml }),
+        RpcMethod::Progress => {
+            let status = instance_control::status(app);
+            return Ok(json!({ "intake_ml": status.intake_ml, "goal_ml": status.goal_ml }));
+        }
+    };
+
+    if let Some(command) = command {
+        let response = instance_control::execute(app, &command);
+        if !response.ok {
+            return Err(RpcError {
+                code: COMMAND_FAILED,
+                message: response.message,
+            });
+        }
+    }
+    serde_json::to_value(instance_control::status(app)).map_err(|err| RpcError {
+        code: COMMAND_FAILED,
+        message: err.to_string(),
+    })
+}
+
+fn send(connection: &Connection, message: &Value) -> Result<(), anyhow::Error> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    connection
+        .lock()
+        .map_err(|e| anyhow!(e.to_string()))?
+        .write_all(&line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let (id, method) = parse_request(r#"{"jsonrpc":"2.0","id":1,"method":"status"}"#).unwrap();
+        assert_eq!(id, Some(json!(1)));
+        assert_eq!(method, RpcMethod::Status);
+
+        let (_, method) =
+            parse_request(r#"{"jsonrpc":"2.0","id":2,"method":"change_time","params":{"Add":5}}"#)
+                .unwrap();
+        assert_eq!(method, RpcMethod::ChangeTime(ChangeTime::Add(5)));
+
+        let (id, method) =
+            parse_request(r#"{"jsonrpc":"2.0","method":"log_intake","params":{"ml":250}}"#)
+                .unwrap();
+        assert_eq!(id, None);
+        assert_eq!(method, RpcMethod::LogIntake { ml: 250 });
+    }
+
+    #[test]
+    fn test_invalid_request() {
+        let (id, err) = parse_request(r#"{"jsonrpc":"2.0","id":3,"method":"quit"}"#).unwrap_err();
+        assert_eq!(id, Some(json!(3)));
+        assert_eq!(err.code, METHOD_NOT_FOUND);
+
+        let (_, err) = parse_request(
+            r#"{"jsonrpc":"2.0","id":5,"method":"log_intake","params":{"ml":"a glass"}}"#,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, INVALID_PARAMS);
+
+        let (_, err) = parse_request(r#"{"jsonrpc":"2.0","id":6,"params":{}}"#).unwrap_err();
+        assert_eq!(err.code, INVALID_REQUEST);
+
+        let (_, err) = parse_request("{ \"id\": 4, ").unwrap_err();
+        assert_eq!(err.code, PARSE_ERROR);
+    }
+
+    #[test]
+    fn test_method_names_match_the_variants() {
+        for name in RpcMethod::VARIANTS {
+            // methods with params fail without them, but never as unknown method
+            if let Err(err) = serde_json::from_value::<RpcMethod>(json!({ "method": name })) {
+                assert!(
+                    !err.to_string().contains("unknown variant"),
+                    "{name}: {err}"
+                );
+            }
+        }
+        assert!(RpcMethod::VARIANTS.contains(&"log_intake"));
+    }
+}
@@ -54,6 +54,52 @@ impl TimerStatus {
         }
     }
 
+    pub fn remaining_seconds(&self) -> u32 {
+        match self {
+            TimerStatus::NotStarted(seconds)
+            | TimerStatus::Active(seconds)
+            | TimerStatus::Paused(_, seconds) => *seconds,
+            TimerStatus::Finished => 0,
+        }
+    }
+
+    /// Short name of the state for integrations.
+    pub fn state_name(&self) -> &'static str {
+        match self {
+            TimerStatus::NotStarted(_) => "not-started",
+            TimerStatus::Active(_) => "active",
+            TimerStatus::Paused(_, _) => "paused",
+            TimerStatus::Finished => "finished",
+        }
+    }
+
+    pub fn pause_reason(&self) -> Option<String> {
+        match self {
+            TimerStatus::Paused(origin, _) => Some(match origin {
+                PauseOrigin::Idle => "idle".to_string(),
+                PauseOrigin::PreventSleep(app_name) => app_name.clone(),
+                PauseOrigin::User => "user".to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Text next to the tray icon, none if the timer is not running.
+    pub fn to_title(&self) -> Option<String> {
+        match self {
+            TimerStatus::NotStarted(_) => None,
+            TimerStatus::Active(duration) => {
+                Some(Duration::from_secs(*duration as u64).to_pretty_time())
+            }
+            TimerStatus::Paused(origin, _) => match origin {
+                PauseOrigin::Idle => Some("Idle".to_string()),
+                PauseOrigin::PreventSleep(_) => Some("Busy".to_string()),
+                PauseOrigin::User => Some("Silent".to_string()),
+            },
+            TimerStatus::Finished => None,
+        }
+    }
+
     pub fn to_text(&self) -> String {
         match self {
             TimerStatus::Active(duration) => Duration::from_secs(*duration as u64).to_pretty_time(),
@@ -75,6 +121,7 @@ pub struct CountdownTimer {
     remaining_time: Arc<Mutex<Duration>>,
     guard: Arc<Mutex<Option<Guard>>>,
     status: Arc<Mutex<TimerStatus>>,
+    tick_speed: Arc<Mutex<chrono::Duration>>,
 }
 
 impl CountdownTimer {
@@ -86,6 +133,7 @@ impl CountdownTimer {
             remaining_time: Arc::new(Mutex::new(Duration::ZERO)),
             guard: Arc::new(Mutex::new(None)),
             status: Arc::new(Mutex::new(TimerStatus::NotStarted(0))),
+            tick_speed: Arc::new(Mutex::new(TICKER_SPEED_MS)),
         }
     }
 
@@ -110,13 +158,32 @@ impl CountdownTimer {
             }
         }
 
+        self.schedule();
+    }
+
+    /// Changes how often the remaining time is updated, e.g. to save power on battery.
+    /// A running countdown continues with its remaining time.
+    pub fn set_tick_speed(&self, tick_speed: chrono::Duration) {
+        {
+            let mut current = self.tick_speed.lock().unwrap();
+            *current = tick_speed;
+        }
+
+        let scheduled = self.guard.lock().unwrap().is_some();
+        if scheduled {
+            self.schedule();
+        }
+    }
+
+    fn schedule(&self) {
+        let tick_speed = *self.tick_speed.lock().unwrap();
         let rem_time = Arc::clone(&self.remaining_time);
         let status = Arc::clone(&self.status);
         let guard_arc = Arc::clone(&self.guard);
         let callback = Arc::clone(&self.tick_callback);
 
         // Schedule the repeating task
-        let guard = self.timer.schedule_repeating(TICKER_SPEED_MS, move || {
+        let guard = self.timer.schedule_repeating(tick_speed, move || {
             // Check if paused
             {
                 let status = { status.lock().unwrap().clone() };
@@ -132,7 +199,7 @@ impl CountdownTimer {
             let rem_time: Duration = {
                 let mut rem_time = rem_time.lock().unwrap();
                 *rem_time = rem_time.saturating_sub(Duration::from_millis(
-                    TICKER_SPEED_MS.num_milliseconds() as u64,
+                    tick_speed.num_milliseconds() as u64,
                 ));
                 rem_time.clone()
             };
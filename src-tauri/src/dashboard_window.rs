@@ -1,6 +1,6 @@
+use crate::cli::Cli;
 use crate::countdown_timer::{PauseOrigin, TimerStatus};
 use crate::{countdown_timer, CountdownTimerState};
-use std::env;
 use tauri::{AppHandle, Manager, Runtime};
 
 pub(crate) const WINDOW_LABEL: &'static str = "dashboard";
@@ -32,9 +32,8 @@ where
     Ok(())
 }
 
-pub fn should_show_dashboard() -> bool {
-    let args: Vec<String> = env::args().collect();
-    !args.contains(&"--quiet".to_string())
+pub fn should_show_dashboard(cli: &Cli) -> bool {
+    !cli.quiet && !cli.headless
 }
 
 #[specta::specta]
@@ -0,0 +1,253 @@
+use crate::countdown_timer::{CountdownEvent, TimerStatus};
+use crate::instance_control::{self, ControlCommand, ControlResponse, InstanceStatus};
+use crate::model::event::IntakeEvent;
+use log::{debug, info};
+use tauri::AppHandle;
+use tauri_specta::Event;
+use zbus::blocking::{connection, Connection};
+use zbus::object_server::SignalEmitter;
+use zbus::{fdo, interface};
+
+pub const BUS_NAME: &str = "app.justdrink.Reminder";
+pub const OBJECT_PATH: &str = "/app/justdrink/Reminder";
+
+/// Operations of the running app offered on the bus.
+pub trait ReminderControl: Send + Sync {
+    fn execute(&self, command: &ControlCommand) -> ControlResponse;
+    fn status(&self) -> InstanceStatus;
+}
+
+impl ReminderControl for AppHandle {
+    fn execute(&self, command: &ControlCommand) -> ControlResponse {
+        instance_control::execute(self, command)
+    }
+
+    fn status(&self) -> InstanceStatus {
+        instance_control::status(self)
+    }
+}
+
+pub struct Reminder {
+    control: Box<dyn ReminderControl>,
+}
+
+impl Reminder {
+    fn run(&self, command: ControlCommand) -> fdo::Result<String> {
+        let response = self.control.execute(&command);
+        if response.ok {
+            Ok(response.message)
+        } else {
+            Err(fdo::Error::Failed(response.message))
+        }
+    }
+}
+
+#[interface(name = "app.justdrink.Reminder")]
+impl Reminder {
+    fn drink_now(&self) -> fdo::Result<String> {
+        self.run(ControlCommand::DrinkNow)
+    }
+
+    /// Pauses the reminders for `seconds`, zero pauses until resumed.
+    fn pause(&self, seconds: u64) -> fdo::Result<String> {
+        self.run(ControlCommand::Pause {
+            seconds: (seconds > 0).then_some(seconds),
+        })
+    }
+
+    fn resume(&self) -> fdo::Result<String> {
+        self.run(ControlCommand::Resume)
+    }
+
+    /// Returns the intake of today.
+    fn log_intake(&self, ml: u32) -> fdo::Result<u32> {
+        self.run(ControlCommand::Log { ml })?;
+        Ok(self.control.status().intake_ml)
+    }
+
+    /// Countdown as shown in the tray.
+    #[zbus(property)]
+    fn status(&self) -> String {
+        self.control.status().timer.to_text()
+    }
+
+    #[zbus(property)]
+    fn remaining_seconds(&self) -> u32 {
+        self.control.status().timer.remaining_seconds()
+    }
+
+    #[zbus(property)]
+    fn today_ml(&self) -> u32 {
+        self.control.status().intake_ml
+    }
+
+    /// Sent with every `CountdownEvent`, `state` is one of not-started, active, paused or finished.
+    #[zbus(signal)]
+    async fn countdown(
+        emitter: &SignalEmitter<'_>,
+        state: &str,
+        remaining_seconds: u32,
+        pause_reason: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Registers the reminder object and claims the bus name.
+pub fn serve(
+    builder: connection::Builder,
+    control: Box<dyn ReminderControl>,
+) -> zbus::Result<Connection> {
+    builder
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, Reminder { control })?
+        .build()
+}
+
+pub fn emit_countdown(connection: &Connection, status: &TimerStatus) -> zbus::Result<()> {
+    let reminder = connection
+        .object_server()
+        .interface::<_, Reminder>(OBJECT_PATH)?;
+    let emitter = reminder.signal_emitter();
+    zbus::block_on(async {
+        Reminder::countdown(
+            emitter,
+            status.state_name(),
+            status.remaining_seconds(),
+            &status.pause_reason().unwrap_or_default(),
+        )
+        .await?;
+        let reminder = reminder.get();
+        reminder.status_changed(emitter).await?;
+        reminder.remaining_seconds_changed(emitter).await
+    })
+}
+
+pub fn emit_intake(connection: &Connection) -> zbus::Result<()> {
+    let reminder = connection
+        .object_server()
+        .interface::<_, Reminder>(OBJECT_PATH)?;
+    let interface = reminder.get();
+    zbus::block_on(interface.today_ml_changed(reminder.signal_emitter()))
+}
+
+pub fn init(app: &AppHandle) -> Result<(), anyhow::Error> {
+    let connection = serve(connection::Builder::session()?, Box::new(app.clone()))?;
+    info!("d-bus service {} available", BUS_NAME);
+
+    let countdown_connection = connection.clone();
+    CountdownEvent::listen(app, move |event| {
+        emit_countdown(&countdown_connection, &event.payload.status)
+            .unwrap_or_else(|err| debug!("unable to send countdown on d-bus: {:?}", err));
+    });
+    IntakeEvent::listen(app, move |_| {
+        emit_intake(&connection)
+            .unwrap_or_else(|err| debug!("unable to send intake on d-bus: {:?}", err));
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::countdown_timer::PauseOrigin;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+
+    /// dbus-daemon only known to this test, stopped when dropped.
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        fn start() -> Option<PrivateBus> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(PrivateBus {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+
+        fn connect(&self) -> connection::Builder<'static> {
+            connection::Builder::address(self.address.as_str()).unwrap()
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            self.daemon.kill().ok();
+        }
+    }
+
+    #[derive(Clone)]
+    struct FakeControl {
+        commands: Arc<Mutex<Vec<ControlCommand>>>,
+    }
+
+    impl ReminderControl for FakeControl {
+        fn execute(&self, command: &ControlCommand) -> ControlResponse {
+            self.commands.lock().unwrap().push(command.clone());
+            ControlResponse {
+                ok: true,
+                message: "done".to_string(),
+                status: None,
+            }
+        }
+
+        fn status(&self) -> InstanceStatus {
+            InstanceStatus {
+                timer: TimerStatus::Paused(PauseOrigin::User, 300),
+                active_profile: None,
+                intake_ml: 750,
+                goal_ml: 2000,
+            }
+        }
+    }
+
+    #[test]
+    fn test_reminder_on_private_bus() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not installed, skip test");
+            return;
+        };
+        let control = FakeControl {
+            commands: Arc::new(Mutex::new(Vec::new())),
+        };
+        let service = serve(bus.connect(), Box::new(control.clone())).unwrap();
+        let client = bus.connect().build().unwrap();
+        let proxy = zbus::blocking::Proxy::new(&client, BUS_NAME, OBJECT_PATH, BUS_NAME).unwrap();
+
+        let _: String = proxy.call("Pause", &(1800u64,)).unwrap();
+        let today: u32 = proxy.call("LogIntake", &(250u32,)).unwrap();
+        assert_eq!(today, 750);
+        assert_eq!(
+            *control.commands.lock().unwrap(),
+            vec![
+                ControlCommand::Pause {
+                    seconds: Some(1800)
+                },
+                ControlCommand::Log { ml: 250 },
+            ]
+        );
+        assert_eq!(proxy.get_property::<u32>("RemainingSeconds").unwrap(), 300);
+        assert_eq!(proxy.get_property::<u32>("TodayMl").unwrap(), 750);
+
+        let mut signals = proxy.receive_signal("Countdown").unwrap();
+        emit_countdown(&service, &TimerStatus::Paused(PauseOrigin::Idle, 300)).unwrap();
+        let signal = signals.next().unwrap();
+        let (state, remaining, reason): (String, u32, String) =
+            signal.body().deserialize().unwrap();
+        assert_eq!(
+            (state.as_str(), remaining, reason.as_str()),
+            ("paused", 300, "idle")
+        );
+    }
+}
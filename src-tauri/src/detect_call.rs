@@ -0,0 +1,196 @@
+#[cfg(target_os = "linux")]
+use log::debug;
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::process::Command;
+
+/// Returns the name of the application currently using the camera or microphone.
+///
+/// A camera is considered in use when any process holds a `/dev/video*` device open,
+/// a microphone when PulseAudio (or PipeWire via pipewire-pulse) reports an uncorked
+/// capture stream which is not recording a monitor source.
+#[cfg(target_os = "linux")]
+pub fn active_call_app() -> Option<String> {
+    camera_app().or_else(microphone_app)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn active_call_app() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn camera_app() -> Option<String> {
+    let own_pid = std::process::id().to_string();
+    let processes = fs::read_dir("/proc").ok()?;
+
+    for process in processes.flatten() {
+        let pid = process.file_name().to_string_lossy().to_string();
+        if !pid.chars().all(|c| c.is_ascii_digit()) || pid == own_pid {
+            continue;
+        }
+
+        // reading fds of other users' processes fails, which is fine
+        let Ok(fds) = fs::read_dir(process.path().join("fd")) else {
+            continue;
+        };
+
+        let uses_camera = fds.flatten().any(|fd| {
+            fs::read_link(fd.path())
+                .map(|target| target.to_string_lossy().starts_with("/dev/video"))
+                .unwrap_or(false)
+        });
+
+        if uses_camera {
+            let name = fs::read_to_string(process.path().join("comm"))
+                .map(|comm| comm.trim().to_string())
+                .unwrap_or_else(|_| "Camera".to_string());
+            return Some(name);
+        }
+    }
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn microphone_app() -> Option<String> {
+    let sources = pactl(&["list", "short", "sources"])?;
+    let source_outputs = pactl(&["list", "source-outputs"])?;
+    parse_capture_app(&sources, &source_outputs)
+}
+
+#[cfg(target_os = "linux")]
+fn pactl(args: &[&str]) -> Option<String> {
+    match Command::new("pactl").args(args).output() {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        Ok(output) => {
+            debug!("pactl {:?} failed with status {}", args, output.status);
+            None
+        }
+        Err(err) => {
+            debug!("pactl not available: {}", err);
+            None
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn parse_capture_app(sources: &str, source_outputs: &str) -> Option<String> {
+    let monitor_sources: Vec<&str> = sources
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let index = columns.next()?;
+            let name = columns.next()?;
+            name.ends_with(".monitor").then_some(index)
+        })
+        .collect();
+
+    source_outputs
+        .split("Source Output #")
+        .skip(1)
+        .find_map(|block| {
+            let mut source = None;
+            let mut corked = false;
+            let mut app_name = None;
+
+            for line in block.lines().map(str::trim) {
+                if let Some(value) = line.strip_prefix("Source:") {
+                    source = Some(value.trim());
+                } else if let Some(value) = line.strip_prefix("Corked:") {
+                    corked = value.trim() == "yes";
+                } else if let Some(value) = line.strip_prefix("application.name = ") {
+                    app_name = Some(value.trim_matches('"').to_string());
+                }
+            }
+
+            let records_monitor = source
+                .map(|source| monitor_sources.contains(&source))
+                .unwrap_or(false);
+
+            if corked || records_monitor {
+                None
+            } else {
+                Some(app_name.unwrap_or_else(|| "Microphone".to_string()))
+            }
+        })
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    const SOURCES: &str = "\
+49\talsa_output.pci-0000_00_1f.3.analog-stereo.monitor\tPipeWire\ts32le 2ch 48000Hz\tSUSPENDED
+50\talsa_input.pci-0000_00_1f.3.analog-stereo\tPipeWire\ts32le 2ch 48000Hz\tRUNNING
+";
+
+    fn source_output(source: u32, corked: &str, app_name: Option<&str>) -> String {
+        let app_name = app_name
+            .map(|name| format!("\t\tapplication.name = \"{name}\"\n"))
+            .unwrap_or_default();
+        format!(
+            "Source Output #112
+\tDriver: PipeWire
+\tOwner Module: n/a
+\tClient: 93
+\tSource: {source}
+\tSample Specification: float32le 1ch 48000Hz
+\tChannel Map: mono
+\tFormat: pcm, format.sample_format = \"\\\"float32le\\\"\"  format.rate = \"48000\"  format.channels = \"1\"  format.channel_map = \"\\\"mono\\\"\"
+\tCorked: {corked}
+\tMute: no
+\tVolume: mono: 65536 / 100% / 0.00 dB
+\t        balance 0.00
+\tBuffer Latency: 0 usec
+\tSource Latency: 0 usec
+\tResample method: PipeWire
+\tProperties:
+{app_name}\t\tmedia.name = \"RecordStream\"
+\t\tnode.rate = \"1/48000\"
+"
+        )
+    }
+
+    #[test]
+    fn test_no_source_outputs() {
+        assert_eq!(parse_capture_app(SOURCES, ""), None);
+    }
+
+    #[test]
+    fn test_recording_microphone() {
+        let outputs = source_output(50, "no", Some("Firefox"));
+        assert_eq!(
+            parse_capture_app(SOURCES, &outputs),
+            Some("Firefox".to_string())
+        );
+    }
+
+    #[test]
+    fn test_corked_stream_is_ignored() {
+        let outputs = source_output(50, "yes", Some("Firefox"));
+        assert_eq!(parse_capture_app(SOURCES, &outputs), None);
+    }
+
+    #[test]
+    fn test_monitor_source_is_ignored() {
+        let outputs = source_output(49, "no", Some("OBS"));
+        assert_eq!(parse_capture_app(SOURCES, &outputs), None);
+    }
+
+    #[test]
+    fn test_first_active_stream_wins() {
+        let outputs = [
+            source_output(49, "no", Some("OBS")),
+            source_output(50, "yes", Some("Firefox")),
+            source_output(50, "no", None),
+        ]
+        .concat();
+        assert_eq!(
+            parse_capture_app(SOURCES, &outputs),
+            Some("Microphone".to_string())
+        );
+    }
+}
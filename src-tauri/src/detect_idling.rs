@@ -1,73 +1,322 @@
+use crate::alert::Alert;
 use crate::countdown_timer::{PauseOrigin, TimerStatus};
-use crate::{session_window, CountdownTimerState, SettingsManagerState};
-use log::{debug, warn};
-use std::thread::sleep;
-use std::time::Duration;
+use crate::idle_time::IdleBackend;
+use crate::model::settings::SettingsUserDetails;
+use crate::{
+    detect_call, power, session_window, CountdownTimerState, IdleMonitorState, SettingsManagerState,
+};
+use log::{debug, info, warn};
+use std::cmp::min;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{sleep, JoinHandle};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Manager, Wry};
-use user_idle::UserIdle;
 
-const IDLE_DURATION_S: u64 = 60;
-const MIN_ACTIVE_DURATION_S: u64 = 20;
+const IDLE_DURATION: Duration = Duration::from_secs(60);
+const MIN_ACTIVE_DURATION: Duration = Duration::from_secs(20);
+const CALL_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const AWAY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_SLEEP: Duration = Duration::from_secs(30);
+const LOW_POWER_INTERVAL_FACTOR: u32 = 4;
+const TYPING_PAUSE_POLL_MS: u64 = 250;
 
-pub enum Mode {
+enum Mode {
     Pause,
     Working,
 }
 
-pub fn init(app: &AppHandle<Wry>) -> Result<(), anyhow::Error> {
-    let app_handle = app.app_handle().clone();
-    tauri::async_runtime::spawn(async move {
-        let timer = app_handle.state::<CountdownTimerState>();
-        let mut mode = Mode::Working;
-        let mut active: u64 = 0;
-        loop {
-            let settings = app_handle.app_handle().state::<SettingsManagerState>();
-            let idle = UserIdle::get_time().unwrap();
+enum Command {
+    SettingsChanged,
+    Stop,
+}
+
+/// Background service pausing the timer while the user is away or in a call.
+///
+/// Both checks have their own setting, the thread only sleeps while both are off.
+///
+/// Instead of polling every second, the monitor sleeps until the idle threshold could be
+/// crossed next, and is woken up early on settings changes or shutdown.
+pub struct IdleMonitor {
+    sender: Mutex<Option<Sender<Command>>>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+    /// Detected once on first use, shared with the wait for a typing pause.
+    backend: Arc<OnceLock<Option<IdleBackend>>>,
+}
 
-            if idle.as_seconds() < IDLE_DURATION_S {
-                active += 1
-            } else {
-                active = 0
+impl IdleMonitor {
+    pub fn start(app: &AppHandle<Wry>) -> Result<Self, anyhow::Error> {
+        let (sender, receiver) = channel();
+
+        let settings_sender = Mutex::new(sender.clone());
+        app.state::<SettingsManagerState>().subscribe(move |_| {
+            if let Ok(sender) = settings_sender.lock() {
+                sender
+                    .send(Command::SettingsChanged)
+                    .unwrap_or_else(|_| debug!("idle monitor already stopped"));
             }
+        });
 
-            if let Some(settings) = settings.get_settings() {
-                let _timer_duration = if let TimerStatus::Active(duration) = timer.timer_status() {
-                    Some(duration)
-                } else {
-                    None
-                };
-
-                if settings.user.active && settings.user.enable_idle_detection {
-                    match mode {
-                        Mode::Pause => {
-                            if active >= MIN_ACTIVE_DURATION_S {
-                                debug!("switch to working");
-                                if matches!(
-                                    timer.timer_status(),
-                                    TimerStatus::Paused(PauseOrigin::Idle, _)
-                                ) || timer.timer_status().is_prevent_sleep()
-                                {
-                                    timer.resume();
-                                }
-                                mode = Mode::Working;
-                            }
-                        }
-                        Mode::Working => {
-                            if idle.as_seconds() > IDLE_DURATION_S {
-                                debug!("switch to pause");
-                                session_window::hide_window(app_handle.app_handle()).unwrap_or_else(|err| {
+        let backend = Arc::new(OnceLock::new());
+        let app_handle = app.app_handle().clone();
+        let run_backend = backend.clone();
+        let handle = std::thread::Builder::new()
+            .name("idle-monitor".to_string())
+            .spawn(move || run(app_handle, receiver, run_backend))?;
+
+        Ok(IdleMonitor {
+            sender: Mutex::new(Some(sender)),
+            handle: Mutex::new(Some(handle)),
+            backend,
+        })
+    }
+
+    /// Returns the idle backend, detecting it on first use.
+    pub fn backend(&self, app: &AppHandle<Wry>) -> Option<&IdleBackend> {
+        detect_backend(app, &self.backend)
+    }
+
+    /// Stops the monitor and waits for the background thread to finish.
+    pub fn stop(&self) {
+        if let Some(sender) = self.sender.lock().unwrap().take() {
+            sender
+                .send(Command::Stop)
+                .unwrap_or_else(|_| debug!("idle monitor already stopped"));
+        }
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle
+                .join()
+                .unwrap_or_else(|_| warn!("idle monitor thread panicked"));
+        }
+    }
+}
+
+fn detect_backend<'a>(
+    app: &AppHandle<Wry>,
+    backend: &'a OnceLock<Option<IdleBackend>>,
+) -> Option<&'a IdleBackend> {
+    backend
+        .get_or_init(|| {
+            IdleBackend::detect()
+                .map_err(|err| {
+                    app.alert(
+                        "Idle detection unavailable",
+                        "Just Drink! can't detect when you are away from your desk on this system. The reminder will keep running while you are idle.",
+                        Some(err),
+                        false,
+                    );
+                })
+                .ok()
+        })
+        .as_ref()
+}
+
+fn run(
+    app_handle: AppHandle<Wry>,
+    receiver: Receiver<Command>,
+    idle_backend: Arc<OnceLock<Option<IdleBackend>>>,
+) {
+    let timer = app_handle.state::<CountdownTimerState>();
+    let mut mode = Mode::Working;
+    let mut active_since: Option<Instant> = None;
+    let mut in_call: Option<String> = None;
+    let mut last_call_check: Option<Instant> = None;
+
+    loop {
+        let (idle_enabled, call_enabled) = app_handle
+            .state::<SettingsManagerState>()
+            .get_settings()
+            .map(|settings| {
+                let user = settings.user;
+                (
+                    user.active && user.enable_idle_detection,
+                    user.active && user.pause_during_calls,
+                )
+            })
+            .unwrap_or((false, false));
+
+        if !call_enabled {
+            if in_call.take().is_some() && timer.timer_status().is_prevent_sleep() {
+                timer.resume();
+            }
+            last_call_check = None;
+        }
+        if !idle_enabled {
+            mode = Mode::Working;
+            active_since = None;
+        }
+
+        if !idle_enabled && !call_enabled {
+            debug!("idle and call detection disabled, wait for settings change");
+            match receiver.recv() {
+                Ok(Command::SettingsChanged) => continue,
+                Ok(Command::Stop) | Err(_) => break,
+            }
+        }
+
+        // checks are spread out while the battery is low, at the cost of reacting later
+        let interval_factor = if power::is_low_power(&app_handle) {
+            LOW_POWER_INTERVAL_FACTOR
+        } else {
+            1
+        };
+        let call_check_interval = CALL_CHECK_INTERVAL * interval_factor;
+        let away_check_interval = AWAY_CHECK_INTERVAL * interval_factor;
+        let mut next_check = MAX_SLEEP * interval_factor;
+
+        if call_enabled {
+            let call_check_due = last_call_check
+                .map(|checked| checked.elapsed() >= call_check_interval)
+                .unwrap_or(true);
+            if call_check_due {
+                in_call = check_call(&app_handle, in_call);
+                last_call_check = Some(Instant::now());
+            }
+            let since_call_check = last_call_check
+                .map(|checked| checked.elapsed())
+                .unwrap_or_default();
+            next_check = min(
+                next_check,
+                call_check_interval.saturating_sub(since_call_check),
+            );
+        }
+
+        let backend = if idle_enabled {
+            detect_backend(&app_handle, &idle_backend)
+        } else {
+            None
+        };
+        if let (Some(backend), None) = (backend, &in_call) {
+            let idle = backend.idle_time().unwrap_or_else(|err| {
+                warn!("unable to read idle time: {err}");
+                Duration::ZERO
+            });
+
+            match mode {
+                Mode::Working => {
+                    if idle > IDLE_DURATION {
+                        debug!("switch to pause");
+                        if timer.timer_status().is_running() {
+                            session_window::hide_window(app_handle.app_handle()).unwrap_or_else(
+                                |err| {
                                     warn!("could not hide session window: {err}");
-                                });
-                                timer.pause(PauseOrigin::Idle);
-                                mode = Mode::Pause;
+                                },
+                            );
+                            timer.pause(PauseOrigin::Idle);
+                        }
+                        mode = Mode::Pause;
+                        active_since = None;
+                        next_check = min(next_check, away_check_interval);
+                    } else {
+                        // the threshold can't be crossed before the remaining idle time passed
+                        next_check =
+                            min(next_check, IDLE_DURATION - idle + Duration::from_secs(1));
+                    }
+                }
+                Mode::Pause => {
+                    if idle < IDLE_DURATION {
+                        let since = *active_since.get_or_insert_with(|| {
+                            Instant::now().checked_sub(idle).unwrap_or_else(Instant::now)
+                        });
+                        if since.elapsed() >= MIN_ACTIVE_DURATION {
+                            debug!("switch to working");
+                            if matches!(
+                                timer.timer_status(),
+                                TimerStatus::Paused(PauseOrigin::Idle, _)
+                            ) {
+                                timer.resume();
                             }
+                            mode = Mode::Working;
+                            active_since = None;
+                            next_check = min(next_check, IDLE_DURATION);
+                        } else {
+                            next_check = min(
+                                next_check,
+                                MIN_ACTIVE_DURATION.saturating_sub(since.elapsed()),
+                            );
                         }
+                    } else {
+                        active_since = None;
+                        next_check = min(next_check, away_check_interval);
                     }
                 }
             }
-            sleep(Duration::from_secs(1));
         }
-    });
 
-    Ok(())
+        match receiver.recv_timeout(next_check) {
+            Ok(Command::SettingsChanged) | Err(RecvTimeoutError::Timeout) => {}
+            Ok(Command::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    info!("idle monitor stopped");
+}
+
+fn check_call(app_handle: &AppHandle<Wry>, in_call: Option<String>) -> Option<String> {
+    let timer = app_handle.state::<CountdownTimerState>();
+    match (detect_call::active_call_app(), in_call) {
+        (Some(app_name), None) => {
+            info!("camera or microphone in use by {app_name}, pause timer");
+            if timer.timer_status().is_running() {
+                session_window::hide_window(app_handle.app_handle()).unwrap_or_else(|err| {
+                    warn!("could not hide session window: {err}");
+                });
+                timer.pause(PauseOrigin::PreventSleep(app_name.clone()));
+            }
+            Some(app_name)
+        }
+        (None, Some(app_name)) => {
+            info!("{app_name} stopped using camera or microphone, resume timer");
+            if timer.timer_status().is_prevent_sleep() {
+                timer.resume();
+            }
+            None
+        }
+        (_, in_call) => in_call,
+    }
+}
+
+/// Waits until the user stopped typing for `typing_pause_seconds`, but never longer than
+/// `typing_pause_max_wait_seconds`. Returns immediately if the mode is disabled.
+pub async fn wait_for_typing_pause(app: &AppHandle<Wry>, user: &SettingsUserDetails) {
+    if !user.wait_for_typing_pause {
+        return;
+    }
+
+    let pause = Duration::from_secs(user.typing_pause_seconds as u64);
+    let max_wait = Duration::from_secs(user.typing_pause_max_wait_seconds as u64);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(idle_monitor) = app.try_state::<IdleMonitorState>() else {
+            warn!("idle monitor not running, show session without waiting");
+            return;
+        };
+        let Some(idle_backend) = idle_monitor.backend(&app) else {
+            warn!("no idle backend, show session without waiting");
+            return;
+        };
+        let started = Instant::now();
+        loop {
+            let idle = match idle_backend.idle_time() {
+                Ok(idle) => idle,
+                Err(err) => {
+                    warn!("unable to read idle time, show session without waiting: {err}");
+                    return;
+                }
+            };
+
+            if idle >= pause {
+                debug!("natural pause after {:?}, show session", started.elapsed());
+                return;
+            }
+            if started.elapsed() >= max_wait {
+                info!("user kept typing for {:?}, show session anyway", max_wait);
+                return;
+            }
+            sleep(Duration::from_millis(TYPING_PAUSE_POLL_MS));
+        }
+    })
+    .await
+    .unwrap_or_else(|err| warn!("waiting for typing pause failed: {err}"));
 }
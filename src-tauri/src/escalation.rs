@@ -0,0 +1,112 @@
+use crate::alert::Alert;
+use crate::countdown_timer::{CountdownEvent, TimerStatus};
+use crate::model::settings::SettingsUserDetails;
+use crate::{cli, notification, session_window, EscalationState};
+use log::info;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_specta::Event;
+
+/// Follow-ups of an unanswered reminder, each after `escalation_minutes`.
+const STEPS: [Step; 2] = [Step::SilentOverlay, Step::Overlay];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    SilentOverlay,
+    Overlay,
+}
+
+/// Escalates a reminder nobody answered: the notification comes first, then the overlay
+/// without sound and at last the overlay with sound. Any answer to the notification or the
+/// overlay, a logged drink or a paused timer ends the escalation.
+pub struct Escalation {
+    /// Id of the running escalation, a new reminder replaces it.
+    current: Arc<Mutex<Option<u64>>>,
+    next_id: AtomicU64,
+}
+
+impl Escalation {
+    pub fn new(app: &AppHandle) -> Self {
+        let current = Arc::new(Mutex::new(None));
+
+        let paused_current = current.clone();
+        CountdownEvent::listen(app, move |event| {
+            if matches!(event.payload.status, TimerStatus::Paused(_, _)) {
+                stop(&paused_current);
+            }
+        });
+
+        Escalation {
+            current,
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    fn begin(&self) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        *self.current.lock().unwrap() = Some(id);
+        id
+    }
+
+    fn finish(&self, id: u64) {
+        let mut current = self.current.lock().unwrap();
+        if *current == Some(id) {
+            *current = None;
+        }
+    }
+
+    fn is_current(&self, id: u64) -> bool {
+        *self.current.lock().unwrap() == Some(id)
+    }
+
+    /// The user reacted to the reminder, no further steps follow.
+    pub fn stop(&self) {
+        stop(&self.current);
+    }
+}
+
+fn stop(current: &Mutex<Option<u64>>) {
+    if current.lock().unwrap().take().is_some() {
+        info!("reminder answered, stop escalation");
+    }
+}
+
+/// Shows the notification and schedules the next steps.
+pub fn start(app: &AppHandle, user: &SettingsUserDetails) -> Result<(), anyhow::Error> {
+    let id = app.state::<EscalationState>().begin();
+    notification::show_reminder(app, user)?;
+
+    let app = app.clone();
+    let user = user.clone();
+    let delay = Duration::from_secs(u64::from(user.escalation_minutes) * 60);
+    thread::spawn(move || {
+        for step in STEPS {
+            thread::sleep(delay);
+            if !app.state::<EscalationState>().is_current(id) {
+                return;
+            }
+            info!("reminder not answered, escalate to {:?}", step);
+            escalate(&app, &user, step).unwrap_or_else(|err| {
+                app.alert(
+                    "Can't escalate reminder",
+                    "Unable to repeat the unanswered reminder.",
+                    Some(err),
+                    true,
+                );
+            });
+        }
+        app.state::<EscalationState>().finish(id);
+    });
+    Ok(())
+}
+
+fn escalate(app: &AppHandle, user: &SettingsUserDetails, step: Step) -> Result<(), anyhow::Error> {
+    // without windows every step repeats the notification
+    if cli::is_headless(app) {
+        return notification::show_reminder(app, user);
+    }
+    session_window::show_escalated_overlay(app, user, step == Step::SilentOverlay)
+}
@@ -93,7 +93,6 @@ pub enum FeedbackRate {
 #[specta::specta]
 #[tauri::command]
 pub fn feedback_window_send_feedback(
-    app: AppHandle,
     feedback: String,
     rating: FeedbackRate,
     feedback_sender: State<FeedbackSenderState>,
@@ -110,7 +109,7 @@ pub fn feedback_window_send_feedback(
         .lock()
         .as_mut()
         .expect("settings_system lock required")
-        .feedback_given(&app);
+        .feedback_given();
     Ok(())
 }
 
@@ -124,7 +123,7 @@ pub fn open_app_store_feedback(
         .lock()
         .as_mut()
         .expect("settings_system lock required")
-        .feedback_given(&app);
+        .feedback_given();
 
     webbrowser::open_browser(
         Browser::Safari,
@@ -0,0 +1,396 @@
+use crate::countdown_timer::{ChangeTime, CountdownEvent};
+use crate::instance_control::{self, ControlCommand};
+use crate::model::event::IntakeEvent;
+use crate::model::settings::SettingsUserDetails;
+use crate::{CountdownTimerState, HttpApiState, SettingsSystemState};
+use anyhow::anyhow;
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::{Read, Write};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_specta::Event;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+#[derive(Debug, PartialEq)]
+enum Route {
+    Status,
+    Stats,
+    Toggle,
+    DrinkNow,
+    Pause,
+    Resume,
+    ChangeTime,
+    Intake,
+    Events,
+}
+
+#[derive(Deserialize, Default)]
+struct PauseRequest {
+    seconds: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct IntakeRequest {
+    ml: u32,
+}
+
+#[derive(Serialize)]
+struct Stats {
+    intake_ml: u32,
+    goal_ml: u32,
+    session_count: u32,
+}
+
+/// Opt-in HTTP interface on 127.0.0.1, every request needs the bearer token.
+pub struct HttpApi {
+    server: Mutex<Option<RunningServer>>,
+    subscribers: Arc<Mutex<Vec<Sender<String>>>>,
+}
+
+struct RunningServer {
+    server: Arc<Server>,
+    handle: JoinHandle<()>,
+}
+
+impl HttpApi {
+    pub fn new(app: &AppHandle) -> Self {
+        let subscribers: Arc<Mutex<Vec<Sender<String>>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let countdown_subscribers = subscribers.clone();
+        CountdownEvent::listen(app, move |event| {
+            broadcast(&countdown_subscribers, "countdown", &event.payload);
+        });
+        let intake_subscribers = subscribers.clone();
+        IntakeEvent::listen(app, move |event| {
+            broadcast(&intake_subscribers, "intake", &event.payload);
+        });
+
+        HttpApi {
+            server: Mutex::new(None),
+            subscribers,
+        }
+    }
+
+    /// Starts, restarts or stops the server as configured by the user.
+    pub fn apply(&self, app: &AppHandle, user: &SettingsUserDetails) -> Result<(), anyhow::Error> {
+        self.stop();
+        if !user.http_api_enabled {
+            return Ok(());
+        }
+
+        // requests are only checked against the token, they never create one
+        app.state::<SettingsSystemState>()
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .ensure_http_api_token()?;
+
+        let port = u16::try_from(user.http_api_port)?;
+        let server = Arc::new(Server::http(("127.0.0.1", port)).map_err(|err| anyhow!(err))?);
+        info!("http api listening on 127.0.0.1:{}", port);
+
+        let app_handle = app.clone();
+        let requests = server.clone();
+        let subscribers = self.subscribers.clone();
+        let handle = thread::Builder::new()
+            .name("http-api".to_string())
+            .spawn(move || {
+                for request in requests.incoming_requests() {
+                    handle(&app_handle, request, &subscribers);
+                }
+                debug!("http api stopped");
+            })?;
+
+        *self.server.lock().map_err(|e| anyhow!(e.to_string()))? =
+            Some(RunningServer { server, handle });
+        Ok(())
+    }
+
+    /// Stops the server and waits for its thread, before the port is bound again.
+    fn stop(&self) {
+        if let Some(running) = self.server.lock().unwrap().take() {
+            info!("stop http api");
+            running.server.unblock();
+            // ends the open event streams
+            self.subscribers.lock().unwrap().clear();
+            // the listener is closed when the last reference to the server is gone
+            drop(running.server);
+            if running.handle.thread().id() != thread::current().id() {
+                running
+                    .handle
+                    .join()
+                    .unwrap_or_else(|_| warn!("http api thread panicked"));
+            }
+        }
+    }
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn http_api_token(app: AppHandle) -> Result<String, String> {
+    app.state::<SettingsSystemState>()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .ensure_http_api_token()
+        .map_err(|e| e.to_string())
+}
+
+/// Creates a new token, clients with the old token are rejected from now on.
+#[specta::specta]
+#[tauri::command]
+pub fn http_api_regenerate_token(app: AppHandle) -> Result<String, String> {
+    info!("regenerate http api token");
+    app.state::<SettingsSystemState>()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .regenerate_http_api_token()
+        .map_err(|e| e.to_string())
+}
+
+pub fn init(app: &AppHandle, user: &SettingsUserDetails) {
+    if let Some(http_api) = app.try_state::<HttpApiState>() {
+        http_api
+            .apply(app, user)
+            .unwrap_or_else(|err| error!("unable to start http api: {:?}", err));
+    }
+}
+
+fn route(method: &Method, path: &str) -> Option<Route> {
+    let route = match (method, path) {
+        (Method::Get, "/v1/status") => Route::Status,
+        (Method::Get, "/v1/stats") => Route::Stats,
+        (Method::Post, "/v1/toggle") => Route::Toggle,
+        (Method::Post, "/v1/drink-now") => Route::DrinkNow,
+        (Method::Post, "/v1/pause") => Route::Pause,
+        (Method::Post, "/v1/resume") => Route::Resume,
+        (Method::Post, "/v1/change-time") => Route::ChangeTime,
+        (Method::Post, "/v1/intake") => Route::Intake,
+        (Method::Get, "/v1/events") => Route::Events,
+        _ => return None,
+    };
+    Some(route)
+}
+
+fn handle(app: &AppHandle, mut request: Request, subscribers: &Mutex<Vec<Sender<String>>>) {
+    let (path, query) = request
+        .url()
+        .split_once('?')
+        .map(|(path, query)| (path.to_string(), Some(query.to_string())))
+        .unwrap_or_else(|| (request.url().to_string(), None));
+
+    let route = route(request.method(), &path);
+    // browsers can't set headers for an EventSource, only the event stream takes the
+    // token from the query, where it may end up in logs and the browser history
+    let query = query.filter(|_| route == Some(Route::Events));
+
+    let response = if !is_authorized(app, &request, query.as_deref()) {
+        error_response(401, "missing or invalid token")
+    } else {
+        match route {
+            None => error_response(404, "unknown endpoint"),
+            Some(Route::Events) => {
+                stream_events(request, subscribers);
+                return;
+            }
+            Some(route) => call(app, route, &mut request).unwrap_or_else(|err| {
+                warn!("http api request {} failed: {:?}", path, err);
+                error_response(400, &err.to_string())
+            }),
+        }
+    };
+    request
+        .respond(response)
+        .unwrap_or_else(|err| debug!("unable to answer http api request: {:?}", err));
+}
+
+fn call(
+    app: &AppHandle,
+    route: Route,
+    request: &mut Request,
+) -> Result<Response<std::io::Cursor<Vec<u8>>>, anyhow::Error> {
+    let command = match route {
+        Route::Status => return json_response(&instance_control::status(app)),
+        Route::Stats => {
+            let status = instance_control::status(app);
+            let session_count = app
+                .state::<SettingsSystemState>()
+                .lock()
+                .map_err(|e| anyhow!(e.to_string()))?
+                .settings()
+                .session_count;
+            return json_response(&Stats {
+                intake_ml: status.intake_ml,
+                goal_ml: status.goal_ml,
+                session_count,
+            });
+        }
+        Route::ChangeTime => {
+            let change_time: ChangeTime = read_json(request)?;
+            app.state::<CountdownTimerState>().change(change_time);
+            return json_response(&instance_control::status(app));
+        }
+        Route::Toggle => ControlCommand::Toggle,
+        Route::DrinkNow => ControlCommand::DrinkNow,
+        Route::Resume => ControlCommand::Resume,
+        Route::Pause => {
+            let pause: PauseRequest = read_optional_json(request)?;
+            ControlCommand::Pause {
+                seconds: pause.seconds,
+            }
+        }
+        Route::Intake => {
+            let intake: IntakeRequest = read_json(request)?;
+            ControlCommand::Log { ml: intake.ml }
+        }
+        Route::Events => return Err(anyhow!("events are streamed")),
+    };
+
+    let response = instance_control::execute(app, &command);
+    let status = if response.ok { 200 } else { 500 };
+    Ok(json_response(&response)?.with_status_code(status))
+}
+
+fn is_authorized(app: &AppHandle, request: &Request, query: Option<&str>) -> bool {
+    let Some(token) = app
+        .state::<SettingsSystemState>()
+        .lock()
+        .ok()
+        .and_then(|system| system.http_api_token())
+    else {
+        return false;
+    };
+
+    let header_token = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .map(str::to_string);
+    let query_token = query.and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+            .map(str::to_string)
+    });
+
+    header_token
+        .or(query_token)
+        .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn read_body(request: &mut Request) -> Result<String, anyhow::Error> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES)
+        .read_to_string(&mut body)?;
+    Ok(body)
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(request: &mut Request) -> Result<T, anyhow::Error> {
+    Ok(serde_json::from_str(&read_body(request)?)?)
+}
+
+/// An empty body stands for the default request.
+fn read_optional_json<T: for<'de> Deserialize<'de> + Default>(
+    request: &mut Request,
+) -> Result<T, anyhow::Error> {
+    let body = read_body(request)?;
+    if body.trim().is_empty() {
+        return Ok(T::default());
+    }
+    Ok(serde_json::from_str(&body)?)
+}
+
+fn json_response<T: Serialize>(
+    value: &T,
+) -> Result<Response<std::io::Cursor<Vec<u8>>>, anyhow::Error> {
+    Ok(Response::from_data(serde_json::to_vec(value)?)
+        .with_header(content_type("application/json")))
+}
+
+fn error_response(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_data(json!({ "error": message }).to_string())
+        .with_status_code(status)
+        .with_header(content_type("application/json"))
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes(&b"Content-Type"[..], value.as_bytes()).expect("valid header")
+}
+
+/// Server-sent events stream, the connection stays open until the client leaves.
+fn stream_events(request: Request, subscribers: &Mutex<Vec<Sender<String>>>) {
+    let (sender, events) = channel();
+    subscribers.lock().unwrap().push(sender);
+
+    thread::spawn(move || {
+        let mut writer = request.into_writer();
+        let mut message = "HTTP/1.1 200 OK\r\n\
+            Content-Type: text/event-stream\r\n\
+            Cache-Control: no-cache\r\n\
+            Connection: close\r\n\r\n"
+            .to_string();
+        loop {
+            if writer
+                .write_all(message.as_bytes())
+                .and_then(|_| writer.flush())
+                .is_err()
+            {
+                break;
+            }
+            message = match events.recv_timeout(KEEP_ALIVE) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => ": keep-alive\n\n".to_string(),
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+        }
+        debug!("http api event stream closed");
+    });
+}
+
+fn broadcast<T: Serialize>(subscribers: &Mutex<Vec<Sender<String>>>, event: &str, payload: &T) {
+    let data = match serde_json::to_string(payload) {
+        Ok(data) => data,
+        Err(err) => {
+            error!("unable to serialize {} event: {:?}", event, err);
+            return;
+        }
+    };
+    let message = format!("event: {}\ndata: {}\n\n", event, data);
+    subscribers
+        .lock()
+        .unwrap()
+        .retain(|subscriber| subscriber.send(message.clone()).is_ok());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route() {
+        assert_eq!(route(&Method::Get, "/v1/status"), Some(Route::Status));
+        assert_eq!(route(&Method::Post, "/v1/intake"), Some(Route::Intake));
+        assert_eq!(route(&Method::Get, "/v1/intake"), None);
+        assert_eq!(route(&Method::Get, "/v2/status"), None);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret!"));
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::anyhow;
+#[cfg(target_os = "linux")]
+use log::debug;
+use log::info;
+use std::time::Duration;
+use user_idle::UserIdle;
+#[cfg(target_os = "linux")]
+use zbus::blocking::Connection;
+
+#[cfg(target_os = "linux")]
+const MUTTER_DESTINATION: &str = "org.gnome.Mutter.IdleMonitor";
+#[cfg(target_os = "linux")]
+const MUTTER_PATH: &str = "/org/gnome/Mutter/IdleMonitor/Core";
+#[cfg(target_os = "linux")]
+const SCREENSAVER_DESTINATION: &str = "org.freedesktop.ScreenSaver";
+#[cfg(target_os = "linux")]
+const SCREENSAVER_PATH: &str = "/org/freedesktop/ScreenSaver";
+
+/// Source of the time since the last user input, chosen at runtime.
+///
+/// `user-idle` relies on X11 on Linux, which does not see input of native Wayland
+/// clients. On Wayland sessions the idle time is read from the compositor via D-Bus
+/// instead, falling back to `user-idle` (XWayland) if no compositor API is available.
+pub enum IdleBackend {
+    UserIdle,
+    #[cfg(target_os = "linux")]
+    MutterIdleMonitor(Connection),
+    #[cfg(target_os = "linux")]
+    ScreenSaver(Connection),
+}
+
+impl IdleBackend {
+    pub fn detect() -> Result<Self, anyhow::Error> {
+        let mut errors = Vec::new();
+
+        #[cfg(target_os = "linux")]
+        if is_wayland_session() {
+            // each backend opens its own connection, try them one after the other
+            let backends: [fn() -> Result<Self, anyhow::Error>; 2] =
+                [Self::mutter, Self::screen_saver];
+            for backend in backends {
+                match backend() {
+                    Ok(backend) => {
+                        info!("use {} for idle detection", backend.name());
+                        return Ok(backend);
+                    }
+                    Err(err) => {
+                        debug!("idle backend not available: {err}");
+                        errors.push(err.to_string());
+                    }
+                }
+            }
+        }
+
+        match Self::UserIdle.idle_time() {
+            Ok(_) => {
+                info!("use {} for idle detection", Self::UserIdle.name());
+                Ok(Self::UserIdle)
+            }
+            Err(err) => {
+                errors.push(err.to_string());
+                Err(anyhow!(
+                    "no idle detection backend available: {}",
+                    errors.join(", ")
+                ))
+            }
+        }
+    }
+
+    pub fn idle_time(&self) -> Result<Duration, anyhow::Error> {
+        match self {
+            IdleBackend::UserIdle => UserIdle::get_time()
+                .map(|idle| Duration::from_secs(idle.as_seconds()))
+                .map_err(|err| anyhow!("user-idle: {:?}", err)),
+            #[cfg(target_os = "linux")]
+            IdleBackend::MutterIdleMonitor(connection) => {
+                let reply = connection.call_method(
+                    Some(MUTTER_DESTINATION),
+                    MUTTER_PATH,
+                    Some(MUTTER_DESTINATION),
+                    "GetIdletime",
+                    &(),
+                )?;
+                let idle_ms: u64 = reply.body().deserialize()?;
+                Ok(Duration::from_millis(idle_ms))
+            }
+            #[cfg(target_os = "linux")]
+            IdleBackend::ScreenSaver(connection) => {
+                let reply = connection.call_method(
+                    Some(SCREENSAVER_DESTINATION),
+                    SCREENSAVER_PATH,
+                    Some(SCREENSAVER_DESTINATION),
+                    "GetSessionIdleTime",
+                    &(),
+                )?;
+                let idle_s: u32 = reply.body().deserialize()?;
+                Ok(Duration::from_secs(idle_s as u64))
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            IdleBackend::UserIdle => "user-idle",
+            #[cfg(target_os = "linux")]
+            IdleBackend::MutterIdleMonitor(_) => "org.gnome.Mutter.IdleMonitor",
+            #[cfg(target_os = "linux")]
+            IdleBackend::ScreenSaver(_) => "org.freedesktop.ScreenSaver",
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn mutter() -> Result<Self, anyhow::Error> {
+        let backend = IdleBackend::MutterIdleMonitor(Connection::session()?);
+        backend.idle_time()?;
+        Ok(backend)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn screen_saver() -> Result<Self, anyhow::Error> {
+        let backend = IdleBackend::ScreenSaver(Connection::session()?);
+        backend.idle_time()?;
+        Ok(backend)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_wayland_session() -> bool {
+    std::env::var("XDG_SESSION_TYPE")
+        .map(|session_type| session_type == "wayland")
+        .unwrap_or(false)
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
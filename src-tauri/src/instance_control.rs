@@ -0,0 +1,410 @@
+use crate::alert::Alert;
+use crate::cli::Cli;
+use crate::countdown_timer::{PauseOrigin, TimerStatus};
+use crate::model::event::IntakeEvent;
+use crate::pretty_time::PrettyTime;
+use crate::settings_validation::INTAKE_ML;
+use crate::status_output;
+use crate::{
+    session_window, CountdownTimerState, EscalationState, SettingsManagerState, SettingsSystemState,
+};
+use anyhow::anyhow;
+use clap::{Parser, Subcommand, ValueEnum};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tauri_specta::Event;
+
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Commands for the running instance, given on the command line or by other integrations.
+#[derive(Subcommand, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum ControlCommand {
+    /// Start a drink reminder now
+    DrinkNow,
+    /// Pause the reminders, for a while like 30m, 2h or 90s, or until resumed
+    Pause {
+        #[arg(value_name = "DURATION", value_parser = parse_duration)]
+        seconds: Option<u64>,
+    },
+    /// Resume the paused reminders
+    Resume,
+    /// Pause the running reminders or resume the paused ones
+    Toggle,
+    /// Log a drink, the amount in ml
+    Log {
+        #[arg(value_parser = clap::value_parser!(u32).range(*INTAKE_ML.start() as i64..=*INTAKE_ML.end() as i64))]
+        ml: u32,
+    },
+    /// Show the countdown and the progress of today
+    Status {
+        /// Output for status bars like waybar or i3blocks
+        #[arg(long, value_enum, default_value_t = StatusFormat::Text)]
+        format: StatusFormat,
+        /// Print the status again whenever it changes
+        #[arg(long)]
+        follow: bool,
+    },
+}
+
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum StatusFormat {
+    Text,
+    Waybar,
+    I3blocks,
+    Json,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ControlResponse {
+    pub ok: bool,
+    pub message: String,
+    pub status: Option<InstanceStatus>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstanceStatus {
+    pub timer: TimerStatus,
+    pub active_profile: Option<String>,
+    pub intake_ml: u32,
+    pub goal_ml: u32,
+}
+
+impl InstanceStatus {
+    pub fn to_text(&self) -> String {
+        let timer = match &self.timer {
+            TimerStatus::Active(_) => format!("Next drink in {}", self.timer.to_text()),
+            _ => self.timer.to_text(),
+        };
+        let profile = self
+            .active_profile
+            .as_ref()
+            .map(|profile| format!(" ({} profile)", profile))
+            .unwrap_or_default();
+        format!(
+            "{}, {} of {} ml today{}",
+            timer, self.intake_ml, self.goal_ml, profile
+        )
+    }
+}
+
+/// Accepts plain minutes or a number with the unit s, m or h.
+fn parse_duration(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let (number, factor) = match value.char_indices().last() {
+        Some((index, 's')) => (&value[..index], 1),
+        Some((index, 'm')) => (&value[..index], 60),
+        Some((index, 'h')) => (&value[..index], 3600),
+        _ => (value, 60),
+    };
+    match number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(factor))
+    {
+        Some(seconds) if seconds > 0 && seconds <= 24 * 3600 => Ok(seconds),
+        _ => Err(format!(
+            "'{}' is no duration like 30m, 2h or 90s up to one day",
+            value
+        )),
+    }
+}
+
+pub fn status(app: &AppHandle) -> InstanceStatus {
+    let settings = app.state::<SettingsManagerState>().get_settings();
+    let intake_ml = app
+        .state::<SettingsSystemState>()
+        .lock()
+        .map(|system| system.intake_today())
+        .unwrap_or_default();
+    InstanceStatus {
+        timer: app.state::<CountdownTimerState>().timer_status(),
+        active_profile: settings.as_ref().map(|s| s.active_profile.clone()),
+        intake_ml,
+        goal_ml: settings.map(|s| s.user.drink_amount_ml).unwrap_or_default(),
+    }
+}
+
+pub fn execute(app: &AppHandle, command: &ControlCommand) -> ControlResponse {
+    info!("execute control command {:?}", command);
+    let timer = app.state::<CountdownTimerState>();
+    let result = match command {
+        ControlCommand::DrinkNow => {
+            let app_handle = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let timer = app_handle.state::<CountdownTimerState>();
+                timer.restart();
+
+                session_window::show_session(&app_handle, None, false)
+                    .await
+                    .unwrap_or_else(|e| {
+                        app_handle.alert(
+                            "Error while starting the session",
+                            "I am sorry, we are unable to start the session.",
+                            Some(e),
+                            false,
+                        );
+                    });
+            });
+            Ok("Time for a drink!".to_string())
+        }
+        ControlCommand::Pause { seconds } => {
+            timer.pause(PauseOrigin::User);
+            match seconds {
+                Some(seconds) => {
+                    resume_later(app, Duration::from_secs(*seconds));
+                    Ok(format!(
+                        "Paused for {}",
+                        Duration::from_secs(*seconds).to_pretty_time()
+                    ))
+                }
+                None => Ok("Paused until resumed".to_string()),
+            }
+        }
+        ControlCommand::Resume => {
+            if matches!(timer.timer_status(), TimerStatus::Paused(_, _)) {
+                timer.resume();
+                Ok("Resumed".to_string())
+            } else {
+                Ok("Not paused".to_string())
+            }
+        }
+        ControlCommand::Toggle => {
+            timer.toggle(PauseOrigin::User);
+            Ok(timer.timer_status().to_text())
+        }
+        ControlCommand::Log { ml } => log_intake(app, *ml).map(|_| format!("Logged {} ml", ml)),
+        ControlCommand::Status { .. } => Ok(status(app).to_text()),
+    };
+
+    match result {
+        Ok(message) => ControlResponse {
+            ok: true,
+            message,
+            status: Some(status(app)),
+        },
+        Err(err) => {
+            error!("control command {:?} failed: {:?}", command, err);
+            ControlResponse {
+                ok: false,
+                message: err.to_string(),
+                status: Some(status(app)),
+            }
+        }
+    }
+}
+
+/// Adds a drink to the intake of today and tells every listener about the progress.
+pub fn log_intake(app: &AppHandle, ml: u32) -> Result<u32, anyhow::Error> {
+    if !INTAKE_ML.contains(&ml) {
+        return Err(anyhow!(
+            "{} ml is not between {} and {}",
+            ml,
+            INTAKE_ML.start(),
+            INTAKE_ML.end()
+        ));
+    }
+    let intake_ml = app
+        .state::<SettingsSystemState>()
+        .lock()
+        .map_err(|e| anyhow!(e.to_string()))?
+        .log_intake(ml)?;
+    // a logged drink answers an escalating reminder
+    if let Some(escalation) = app.try_state::<EscalationState>() {
+        escalation.stop();
+    }
+    IntakeEvent {
+        intake_ml,
+        goal_ml: status(app).goal_ml,
+    }
+    .emit(app)?;
+    Ok(intake_ml)
+}
+
+/// Resumes the user pause after `duration`, unless it was resumed or paused again meanwhile.
+fn resume_later(app: &AppHandle, duration: Duration) {
+    let app = app.clone();
+    let paused = app.state::<CountdownTimerState>().timer_status();
+    thread::spawn(move || {
+        thread::sleep(duration);
+        let timer = app.state::<CountdownTimerState>();
+        if timer.timer_status() == paused {
+            info!("pause is over, resume timer");
+            timer.resume();
+        }
+    });
+}
+
+/// Called with the arguments of a second instance. Returns `false` if they contain no command.
+pub fn handle_forwarded(app: &AppHandle, args: &[String]) -> bool {
+    let cli = match Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        Err(err) => {
+            warn!("unable to parse arguments of second instance: {}", err);
+            return false;
+        }
+    };
+    let Some(command) = cli.command else {
+        return false;
+    };
+
+    let response = execute(app, &command);
+    if let Some(reply_to) = cli.reply_to {
+        write_reply(&reply_to, &response)
+            .unwrap_or_else(|err| error!("unable to reply to {:?}: {:?}", reply_to, err));
+    }
+    true
+}
+
+/// Answers a forwarded command if this process turned out to be the only instance.
+pub fn reply_not_running(reply_to: &Path) {
+    let response = ControlResponse {
+        ok: false,
+        message: "Just Drink! is not running".to_string(),
+        status: None,
+    };
+    write_reply(reply_to, &response)
+        .unwrap_or_else(|err| error!("unable to reply to {:?}: {:?}", reply_to, err));
+}
+
+/// Never follows or overwrites an existing file, the reader waits for complete json.
+fn write_reply(reply_to: &Path, response: &ControlResponse) -> Result<(), anyhow::Error> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(reply_to)?;
+    file.write_all(&serde_json::to_vec(response)?)?;
+    Ok(())
+}
+
+/// Sends the command of this process to the running instance and prints the reply.
+///
+/// The single instance plugin exits the second instance after handing over its arguments,
+/// so the command runs in a child process and the running instance writes the reply to a file.
+/// Returns the exit code.
+pub fn forward(cli: &Cli) -> i32 {
+    if let Some(ControlCommand::Status { format, follow }) = &cli.command {
+        if *follow || *format != StatusFormat::Text {
+            return status_output::run(*format, *follow);
+        }
+    }
+    match forward_command(cli) {
+        Ok(response) => {
+            if response.ok {
+                println!("{}", response.message);
+                0
+            } else {
+                eprintln!("{}", response.message);
+                1
+            }
+        }
+        Err(err) => {
+            eprintln!("Unable to reach Just Drink!: {}", err);
+            1
+        }
+    }
+}
+
+fn forward_command(cli: &Cli) -> Result<ControlResponse, anyhow::Error> {
+    // a directory only this user can access, removed again when dropped
+    let reply_dir = tempfile::Builder::new()
+        .prefix("justdrink-")
+        .tempdir_in(runtime_dir())?;
+    let reply_to = reply_dir.path().join("reply.json");
+
+    let mut child = Command::new(std::env::current_exe()?)
+        .arg("--reply-to")
+        .arg(&reply_to)
+        .args(std::env::args_os().skip(1))
+        .spawn()?;
+
+    let started = Instant::now();
+    let response = loop {
+        let reply = fs::read_to_string(&reply_to)
+            .ok()
+            .and_then(|json| serde_json::from_str::<ControlResponse>(&json).ok());
+        if let Some(response) = reply {
+            break response;
+        }
+        if started.elapsed() > REPLY_TIMEOUT {
+            child.kill().ok();
+            return Err(anyhow!("no answer to {:?}", cli.command));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    child.wait()?;
+    Ok(response)
+}
+
+/// `$XDG_RUNTIME_DIR` is private to the user, the shared temp dir is the fallback.
+fn runtime_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30"), Ok(1800));
+        assert_eq!(parse_duration("30m"), Ok(1800));
+        assert_eq!(parse_duration("2h"), Ok(7200));
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("2d").is_err());
+        assert!(parse_duration("25h").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn test_cli_commands() {
+        let cli = Cli::try_parse_from(["justdrink", "pause", "30m"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(ControlCommand::Pause {
+                seconds: Some(1800)
+            })
+        );
+        let cli =
+            Cli::try_parse_from(["justdrink", "status", "--format", "waybar", "--follow"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(ControlCommand::Status {
+                format: StatusFormat::Waybar,
+                follow: true
+            })
+        );
+        let cli = Cli::try_parse_from(["justdrink", "log", "250"]).unwrap();
+        assert_eq!(cli.command, Some(ControlCommand::Log { ml: 250 }));
+        assert!(Cli::try_parse_from(["justdrink", "log", "0"]).is_err());
+        assert_eq!(Cli::try_parse_from(["justdrink"]).unwrap().command, None);
+    }
+    #[test]
+    fn test_reply_is_never_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let reply_to = dir.path().join("reply.json");
+        let response = ControlResponse {
+            ok: true,
+            message: "Paused".to_string(),
+            status: None,
+        };
+        write_reply(&reply_to, &response).unwrap();
+        assert!(write_reply(&reply_to, &response).is_err());
+
+        let written: ControlResponse =
+            serde_json::from_str(&fs::read_to_string(&reply_to).unwrap()).unwrap();
+        assert_eq!(written.message, "Paused");
+    }
+}
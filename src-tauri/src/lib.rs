@@ -1,19 +1,41 @@
 mod alert;
+mod cli;
+#[cfg(unix)]
+mod control_socket;
 mod countdown_timer;
+#[cfg(target_os = "linux")]
+mod dbus_service;
+mod detect_call;
 mod detect_idling;
+mod escalation;
+mod idle_time;
+mod instance_control;
 mod model;
+mod notification;
+mod overlay_monitors;
+mod power;
 mod pretty_time;
 mod tracking;
 mod tray;
+mod worker;
 
 mod app_config;
 mod dashboard_window;
 mod feedback_window;
+mod http_api;
 mod license_manager;
 mod session_window;
+mod settings_backup;
 mod settings_manager;
+mod settings_migration;
+mod settings_overrides;
+mod settings_policy;
+mod settings_profiles;
+mod settings_storage;
 mod settings_system;
+mod settings_validation;
 mod settings_window;
+mod status_output;
 mod subscription_manager;
 mod updater_window;
 mod welcome_window;
@@ -30,6 +52,7 @@ use tauri::ActivationPolicy;
 use crate::countdown_timer::CountdownTimer;
 
 use crate::alert::Alert;
+use crate::cli::Cli;
 use crate::settings_manager::SettingsManager;
 use crate::settings_system::SettingsSystem;
 use crate::tracking::Tracking;
@@ -39,6 +62,7 @@ use tauri_plugin_autostart::MacosLauncher;
 use tauri_plugin_log::Target;
 use tauri_specta::{collect_commands, collect_events, Builder, Commands, ErrorHandlingMode, Events};
 
+type CliState = Cli;
 type FeedbackSenderState = feedback_window::FeedbackSender;
 type SettingsManagerState = SettingsManager;
 type SettingsSystemState = Mutex<SettingsSystem>;
@@ -46,9 +70,20 @@ type CountdownTimerState = CountdownTimer;
 type TrackingState = Tracking;
 type LicenseManagerState = license_manager::LicenseManager;
 type SubscriptionManagerState = subscription_manager::SubscriptionManager;
+type IdleMonitorState = detect_idling::IdleMonitor;
+type HttpApiState = http_api::HttpApi;
+type EscalationState = escalation::Escalation;
+type PowerMonitorState = power::PowerMonitor;
+type ProfileSchedulerState = settings_profiles::ProfileScheduler;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let cli = Cli::parse_or_default();
+    if cli.command.is_some() && cli.reply_to.is_none() {
+        std::process::exit(instance_control::forward(&cli));
+    }
+    let log_level = cli.log_level;
+
     let builder = build_typescript_interfaces(
         collect_commands![
             alert::alert_log_client_error,
@@ -60,11 +95,21 @@ pub fn run() {
             feedback_window::open_app_store_feedback,
             session_window::start_session,
             session_window::end_session,
+            session_window::preview_sound,
             settings_window::open_settings,
             settings_window::load_settings,
             settings_window::get_device_id,
             settings_window::update_settings,
             settings_window::open_browser,
+            settings_backup::settings_export,
+            settings_backup::settings_import_preview,
+            settings_backup::settings_import,
+            settings_profiles::load_profiles,
+            settings_profiles::switch_profile,
+            settings_profiles::save_profile,
+            settings_profiles::delete_profile,
+            http_api::http_api_token,
+            http_api::http_api_regenerate_token,
             welcome_window::welcome_with,
             welcome_window::welcome_load_settings,
             welcome_window::welcome_redo,
@@ -78,9 +123,11 @@ pub fn run() {
         ],
         collect_events![
             model::event::SessionStartEvent,
+            model::event::IntakeEvent,
             model::settings::Settings,
             model::settings::WelcomeWizardMode,
             model::settings::SettingsUserDetails,
+            model::settings::SettingsChangedEvent,
             license_manager::LicenseResult,
             countdown_timer::CountdownEvent,
             countdown_timer::TimerStatus,
@@ -90,12 +137,15 @@ pub fn run() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_process::init())
-        .plugin(tauri_plugin_single_instance::init(|_app, _args, _cwd| {
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            if instance_control::handle_forwarded(app, &args) {
+                return;
+            }
             info!("open dashboard - only on windows");
             #[cfg(target_os = "windows")]
             {
                 info!("instance of Just Drink! already open");
-                show_dashboard(_app);
+                show_dashboard(app);
             }
         }))
         .plugin(tauri_plugin_updater::Builder::new().build())
@@ -139,13 +189,13 @@ pub fn run() {
                 )
                 .level_for("tao::platform_impl::platform::view", log::LevelFilter::Info)
                 .level_for("tauri_plugin_aptabase::dispatcher", log::LevelFilter::Info)
-                .level(log::LevelFilter::Trace)
+                .level(log_level)
                 .build(),
         )
         .invoke_handler(builder.invoke_handler())
         .enable_macos_default_menu(false)
         .setup(move |app| {
-            setup::setup(builder, app)
+            setup::setup(builder, cli, app)
         })
         .on_window_event(|window, event| match event {
             WindowEvent::CloseRequested { api, .. } => {
@@ -177,6 +227,15 @@ pub fn run() {
                 app.track_event("app_quit", None);
                 let timer = app.state::<CountdownTimerState>();
                 timer.stop();
+                if let Some(idle_monitor) = app.try_state::<IdleMonitorState>() {
+                    idle_monitor.stop();
+                }
+                if let Some(power_monitor) = app.try_state::<PowerMonitorState>() {
+                    power_monitor.stop();
+                }
+                if let Some(profile_scheduler) = app.try_state::<ProfileSchedulerState>() {
+                    profile_scheduler.stop();
+                }
             }
             _ => {}
         })
@@ -184,6 +243,10 @@ pub fn run() {
 
 
 fn show_dashboard(app: &AppHandle) {
+    if cli::is_headless(app) {
+        info!("headless, no dashboard");
+        return;
+    }
     dashboard_window::show(app.app_handle()).unwrap_or_else(|err| {
         app.alert(
             "Can't open action menu",
@@ -1,3 +1,4 @@
+pub mod backup;
 pub mod device;
 pub mod event;
 pub mod license;
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri_specta::Event;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event, PartialEq)]
+pub struct SettingsChange {
+    pub(crate) field: String,
+    pub(crate) current: Option<String>,
+    /// Missing if the import removes the profile.
+    pub(crate) imported: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event)]
+pub struct SettingsImportPreview {
+    pub(crate) exported_by_version: String,
+    pub(crate) exported_at: String,
+    pub(crate) changes: Vec<SettingsChange>,
+}
@@ -1,4 +1,4 @@
-use crate::model::session::{DrinkCharacter, SipSize};
+use crate::model::session::{DrinkCharacter, OverlayLayout, ReminderSound, SipSize};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
@@ -6,5 +6,19 @@ use specta::Type;
 pub struct SessionStartEvent {
     pub(crate) selected_drink_character: DrinkCharacter,
     pub(crate) sip_size: SipSize,
-    pub(crate) demo_mode: bool
+    pub(crate) demo_mode: bool,
+    /// Overlay without sound, the first overlay of an escalating reminder or during
+    /// quiet hours.
+    pub(crate) muted: bool,
+    pub(crate) sound: ReminderSound,
+    /// Volume in percent.
+    pub(crate) volume: u32,
+    pub(crate) layout: OverlayLayout,
+}
+
+/// Water logged today, sent whenever a drink is logged.
+#[derive(Serialize, Deserialize, Debug, Clone, Type, tauri_specta::Event)]
+pub struct IntakeEvent {
+    pub(crate) intake_ml: u32,
+    pub(crate) goal_ml: u32,
 }
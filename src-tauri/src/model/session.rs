@@ -15,6 +15,78 @@ pub enum SipSize {
     FullCup,
 }
 
+impl SipSize {
+    /// Amount logged for a reminder answered with "Drank". The same amounts as in the welcome
+    /// wizard (`src/routes/welcome/SipSize.ts`), which derives the reminder interval from
+    /// `drink_amount_ml` and the sip size, so the logged sips add up to the daily goal.
+    pub fn ml(&self) -> u32 {
+        match self {
+            SipSize::BigSip => 45,
+            SipSize::HalfCup => 165,
+            SipSize::FullCup => 330,
+        }
+    }
+}
+
+/// How a drink reminder is presented.
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event, PartialEq)]
+pub enum ReminderStyle {
+    /// Full screen video overlay
+    Overlay,
+    /// Native desktop notification with action buttons
+    Notification,
+}
+
+/// Sound of the reminder overlay.
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event, PartialEq)]
+pub enum ReminderSound {
+    /// Gulps of the drink character
+    Character,
+    /// Sparkling water only
+    Sparkling,
+    /// Audio file of the user, mp3, ogg or wav
+    File(String),
+}
+
+/// Monitors showing the reminder overlay.
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event, PartialEq)]
+pub enum OverlayMonitor {
+    /// Primary monitor of the system
+    Primary,
+    /// Monitor with the mouse cursor when the reminder starts
+    Cursor,
+    /// Every connected monitor, the sound plays once
+    All,
+}
+
+/// Placement of the reminder overlay on its monitor.
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event, PartialEq)]
+pub enum OverlayPlacement {
+    /// Covers the whole monitor
+    FullScreen,
+    /// Widget in the bottom right corner
+    Corner,
+}
+
+/// Layout of the reminder overlay, the backend sizes the window and the page draws it.
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event, PartialEq)]
+pub struct OverlayLayout {
+    pub(crate) placement: OverlayPlacement,
+    /// Width of the corner widget in percent of the monitor width.
+    pub(crate) size_percent: u32,
+    /// Opacity of the overlay in percent.
+    pub(crate) opacity: u32,
+    /// Clicks and keys confirm the reminder instead of passing through to the windows below.
+    pub(crate) interactive: bool,
+}
+
+/// Answer of the user to a drink reminder.
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event, PartialEq)]
+pub enum SessionOutcome {
+    Drank,
+    Snooze,
+    Skip,
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone, Type, Event)]
 pub enum GenderType {
@@ -1,5 +1,8 @@
-use crate::model::session::{DrinkCharacter, GenderType, SipSize};
-use chrono::{DateTime, Utc};
+use crate::model::session::{
+    DrinkCharacter, GenderType, OverlayLayout, OverlayMonitor, OverlayPlacement, ReminderSound,
+    ReminderStyle, SipSize,
+};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use tauri_specta::Event;
@@ -25,6 +28,9 @@ pub struct Settings {
     pub(crate) app: AppDetails,
     pub(crate) user: SettingsUserDetails,
     pub(crate) selected_tab: SettingsTabs,
+    /// Fields enforced by the administrator policy, read-only for the user.
+    pub(crate) locked_fields: Vec<String>,
+    pub(crate) min_break_duration_minutes: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Type, Event)]
@@ -47,6 +53,117 @@ pub struct SettingsUserDetails {
     pub(crate) enable_on_startup: bool,
     pub(crate) beta_version: bool,
     pub(crate) enable_idle_detection: bool,
+    /// Pauses the timer while the camera or microphone is in use.
+    #[serde(default = "default_pause_during_calls")]
+    pub(crate) pause_during_calls: bool,
+    #[serde(default = "default_wait_for_typing_pause")]
+    pub(crate) wait_for_typing_pause: bool,
+    #[serde(default = "default_typing_pause_seconds")]
+    pub(crate) typing_pause_seconds: u32,
+    #[serde(default = "default_typing_pause_max_wait_seconds")]
+    pub(crate) typing_pause_max_wait_seconds: u32,
+    #[serde(default = "default_notification_on_battery")]
+    pub(crate) notification_on_battery: bool,
+    #[serde(default = "default_reduce_work_on_low_battery")]
+    pub(crate) reduce_work_on_low_battery: bool,
+    /// Local HTTP interface for integrations, protected by a bearer token.
+    #[serde(default = "default_http_api_enabled")]
+    pub(crate) http_api_enabled: bool,
+    #[serde(default = "default_http_api_port")]
+    pub(crate) http_api_port: u32,
+    #[serde(default = "default_reminder_style")]
+    pub(crate) reminder_style: ReminderStyle,
+    /// Follow up an unanswered reminder with the overlay, then with the overlay and sound.
+    #[serde(default = "default_escalate_reminders")]
+    pub(crate) escalate_reminders: bool,
+    #[serde(default = "default_escalation_minutes")]
+    pub(crate) escalation_minutes: u32,
+    /// Minutes until the next reminder after "Snooze".
+    #[serde(default = "default_snooze_minutes")]
+    pub(crate) snooze_minutes: u32,
+    #[serde(default = "default_reminder_sound")]
+    pub(crate) reminder_sound: ReminderSound,
+    /// Volume of the reminder sound in percent.
+    #[serde(default = "default_sound_volume")]
+    pub(crate) sound_volume: u32,
+    /// Mute the reminder sound between the start and end of the quiet hours, given in
+    /// minutes since midnight. Quiet hours ending before they start span midnight.
+    #[serde(default = "default_mute_during_quiet_hours")]
+    pub(crate) mute_during_quiet_hours: bool,
+    #[serde(default = "default_quiet_hours_start_minute")]
+    pub(crate) quiet_hours_start_minute: u32,
+    #[serde(default = "default_quiet_hours_end_minute")]
+    pub(crate) quiet_hours_end_minute: u32,
+    #[serde(default = "default_overlay_monitor")]
+    pub(crate) overlay_monitor: OverlayMonitor,
+    #[serde(default = "default_overlay_layout")]
+    pub(crate) overlay_layout: OverlayLayout,
+}
+
+impl SettingsUserDetails {
+    /// True if the reminder sound is muted at `minute` since midnight.
+    pub fn is_quiet(&self, minute: u32) -> bool {
+        self.mute_during_quiet_hours
+            && in_window(
+                self.quiet_hours_start_minute,
+                self.quiet_hours_end_minute,
+                minute,
+            )
+    }
+}
+
+/// True if `minute` since midnight lies in the window from `start` up to, but not
+/// including, `end`. A window ending before it starts spans midnight.
+fn in_window(start: u32, end: u32, minute: u32) -> bool {
+    if start <= end {
+        (start..end).contains(&minute)
+    } else {
+        minute >= start || minute < end
+    }
+}
+
+/// Named set of user settings, only one profile is active at a time.
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event)]
+pub struct SettingsProfile {
+    pub(crate) name: String,
+    pub(crate) user: SettingsUserDetails,
+    pub(crate) schedule: Option<ProfileSchedule>,
+}
+
+/// Local time window in which a profile is selected automatically.
+/// Days are numbered from 1 (Monday) to 7 (Sunday), times are minutes since midnight.
+/// A window ending before it starts spans midnight.
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event, PartialEq)]
+pub struct ProfileSchedule {
+    pub(crate) days: Vec<u32>,
+    pub(crate) start_minute: u32,
+    pub(crate) end_minute: u32,
+}
+
+impl ProfileSchedule {
+    pub fn matches(&self, day: u32, minute: u32) -> bool {
+        self.days.contains(&day) && in_window(self.start_minute, self.end_minute, minute)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event)]
+pub struct SettingsProfiles {
+    pub(crate) active_profile: String,
+    pub(crate) profiles: Vec<SettingsProfile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Type, Event)]
+pub struct SettingsChangedEvent {
+    pub(crate) changed_fields: Vec<String>,
+    pub(crate) user: SettingsUserDetails,
+    pub(crate) active_profile: String,
+    pub(crate) profile_names: Vec<String>,
+}
+
+impl SettingsChangedEvent {
+    pub fn has_changed(&self, field: &str) -> bool {
+        self.changed_fields.iter().any(|changed| changed == field)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -56,11 +173,85 @@ pub struct SettingsSystemDetails {
     #[serde(default = "default_feedback_provided")]
     pub(crate) feedback_provided: bool,
     pub(crate) last_update_check_date: DateTime<Utc>,
+    /// Day of the logged intake, the intake starts at zero every day.
+    #[serde(default)]
+    pub(crate) intake_date: Option<NaiveDate>,
+    #[serde(default = "default_intake_ml")]
+    pub(crate) intake_ml: u32,
+    #[serde(default)]
+    pub(crate) http_api_token: Option<String>,
+}
+
+pub(crate) fn default_pause_during_calls() -> bool {
+    true
+}
+pub(crate) fn default_wait_for_typing_pause() -> bool {
+    false
+}
+pub(crate) fn default_typing_pause_seconds() -> u32 {
+    3
+}
+pub(crate) fn default_typing_pause_max_wait_seconds() -> u32 {
+    60
+}
+pub(crate) fn default_notification_on_battery() -> bool {
+    false
+}
+pub(crate) fn default_reduce_work_on_low_battery() -> bool {
+    true
+}
+
+pub(crate) fn default_http_api_enabled() -> bool {
+    false
+}
+pub(crate) fn default_http_api_port() -> u32 {
+    27_474
+}
+pub(crate) fn default_reminder_style() -> ReminderStyle {
+    ReminderStyle::Overlay
+}
+pub(crate) fn default_escalate_reminders() -> bool {
+    false
+}
+pub(crate) fn default_escalation_minutes() -> u32 {
+    5
+}
+pub(crate) fn default_snooze_minutes() -> u32 {
+    5
+}
+pub(crate) fn default_reminder_sound() -> ReminderSound {
+    ReminderSound::Character
+}
+pub(crate) fn default_sound_volume() -> u32 {
+    80
+}
+pub(crate) fn default_mute_during_quiet_hours() -> bool {
+    false
+}
+pub(crate) fn default_quiet_hours_start_minute() -> u32 {
+    22 * 60
+}
+pub(crate) fn default_quiet_hours_end_minute() -> u32 {
+    7 * 60
+}
+pub(crate) fn default_overlay_monitor() -> OverlayMonitor {
+    OverlayMonitor::Primary
+}
+pub(crate) fn default_overlay_layout() -> OverlayLayout {
+    OverlayLayout {
+        placement: OverlayPlacement::FullScreen,
+        size_percent: 25,
+        opacity: 80,
+        interactive: false,
+    }
 }
 
 fn default_session_count() -> u32 {
     0
 }
+fn default_intake_ml() -> u32 {
+    0
+}
 fn default_feedback_provided() -> bool {
     false
 }
@@ -0,0 +1,93 @@
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::alert::Alert;
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::model::session::SessionOutcome;
+use crate::model::session::SipSize;
+use crate::model::settings::SettingsUserDetails;
+use log::info;
+use notify_rust::Notification;
+use tauri::AppHandle;
+
+const APP_NAME: &str = "Just Drink!";
+
+/// Action ids and button labels of the reminder notification.
+#[cfg(all(unix, not(target_os = "macos")))]
+const ACTIONS: [(&str, &str); 3] = [("drank", "Drank"), ("snooze", "Snooze"), ("skip", "Skip")];
+
+/// Shows the drink reminder as a native desktop notification, a lighter alternative
+/// to the full screen session overlay.
+///
+/// With freedesktop notifications the buttons "Drank", "Snooze" and "Skip" end the
+/// session like the overlay does, other platforms show the reminder only.
+pub fn show_reminder(app: &AppHandle, user: &SettingsUserDetails) -> Result<(), anyhow::Error> {
+    info!("show reminder as notification");
+    let mut notification = Notification::new();
+    notification
+        .appname(APP_NAME)
+        .summary("Time for a drink!")
+        .body(reminder_text(&user.sip_size));
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        for (action, label) in ACTIONS {
+            notification.action(action, label);
+        }
+        let handle = notification.show()?;
+        let app = app.clone();
+        std::thread::spawn(move || {
+            handle.wait_for_action(|action| match outcome(action) {
+                Some(outcome) => {
+                    tauri::async_runtime::block_on(crate::session_window::finish_session(
+                        &app,
+                        Some(outcome),
+                        false,
+                    ))
+                    .unwrap_or_else(|err| {
+                        app.alert(
+                            "Can't end session",
+                            "Unable to handle the answer to the reminder.",
+                            Some(err),
+                            true,
+                        );
+                    });
+                }
+                None => log::debug!("reminder notification closed with {}", action),
+            });
+        });
+    }
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    {
+        let _ = app;
+        notification.show()?;
+    }
+    Ok(())
+}
+
+/// Shows an error as notification where no alert window can be opened.
+pub fn show_message(title: &str, message: &str) -> Result<(), anyhow::Error> {
+    Notification::new()
+        .appname(APP_NAME)
+        .summary(title)
+        .body(message)
+        .show()?;
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn outcome(action: &str) -> Option<SessionOutcome> {
+    match action {
+        "drank" => Some(SessionOutcome::Drank),
+        "snooze" => Some(SessionOutcome::Snooze),
+        "skip" => Some(SessionOutcome::Skip),
+        // dismissed or expired
+        _ => None,
+    }
+}
+
+fn reminder_text(sip_size: &SipSize) -> &'static str {
+    match sip_size {
+        SipSize::BigSip => "Take a big sip of water.",
+        SipSize::HalfCup => "Drink half a cup of water.",
+        SipSize::FullCup => "Drink a full cup of water.",
+    }
+}
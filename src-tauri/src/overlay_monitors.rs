@@ -0,0 +1,177 @@
+use crate::model::session::{OverlayLayout, OverlayMonitor, OverlayPlacement};
+use crate::model::settings::{default_overlay_layout, default_overlay_monitor};
+use crate::{session_window, SettingsManagerState};
+use log::{debug, warn};
+use mouse_position::mouse_position::Mouse;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewWindow, Wry};
+
+/// Gap of the corner widget to the monitor edges, a fraction of the monitor height.
+const CORNER_MARGIN_DIVISOR: u32 = 40;
+
+/// Serializes the placement, a settings change and a starting reminder may place at the same time.
+static PLACEMENT: Mutex<()> = Mutex::new(());
+
+/// Places the session windows, every reminder arranges them again for the current monitors
+/// when it starts and when it ends.
+pub fn init(app: &AppHandle<Wry>) -> Result<(), anyhow::Error> {
+    place(app)
+}
+
+/// Arranges the session windows with the monitor and layout of the user settings.
+pub fn place(app: &AppHandle<Wry>) -> Result<(), anyhow::Error> {
+    let layout = app
+        .state::<SettingsManagerState>()
+        .get_settings()
+        .map(|s| s.user.overlay_layout)
+        .unwrap_or_else(default_overlay_layout);
+    arrange(app, &layout)
+}
+
+/// Moves the session window onto the monitor of the user settings. With all monitors
+/// selected every further monitor gets a mirror window, mirrors of removed monitors close.
+pub fn arrange(app: &AppHandle<Wry>, layout: &OverlayLayout) -> Result<(), anyhow::Error> {
+    let _placement = PLACEMENT.lock().unwrap();
+    let selection = app
+        .state::<SettingsManagerState>()
+        .get_settings()
+        .map(|s| s.user.overlay_monitor)
+        .unwrap_or_else(default_overlay_monitor);
+    let monitors = selected_monitors(app, &selection)?;
+
+    let mut labels = Vec::new();
+    for (index, monitor) in monitors.iter().enumerate() {
+        let label = session_window::window_label(index);
+        let window = match app.get_webview_window(&label) {
+            Some(window) => window,
+            None => session_window::build_session_window(app, &label)?,
+        };
+        move_to(&window, monitor, layout)?;
+        // only the main window answers the reminder
+        window.set_ignore_cursor_events(!(layout.interactive && index == 0))?;
+        labels.push(label);
+    }
+
+    for (label, window) in app.webview_windows() {
+        if session_window::is_mirror(&label) && !labels.contains(&label) {
+            debug!("close session window {label}");
+            window.destroy()?;
+        }
+    }
+    Ok(())
+}
+
+/// Monitors for the overlay, the first one gets the session window playing the sound.
+fn selected_monitors(
+    app: &AppHandle<Wry>,
+    selection: &OverlayMonitor,
+) -> Result<Vec<Monitor>, anyhow::Error> {
+    let monitors = app.available_monitors()?;
+    let primary = app.primary_monitor()?.or_else(|| monitors.first().cloned());
+
+    Ok(match selection {
+        OverlayMonitor::Primary => primary.into_iter().collect(),
+        OverlayMonitor::Cursor => cursor_monitor(&monitors).or(primary).into_iter().collect(),
+        OverlayMonitor::All => {
+            let primary_position = primary.as_ref().map(|primary| *primary.position());
+            let mut monitors = monitors;
+            monitors.sort_by_key(|monitor| Some(*monitor.position()) != primary_position);
+            monitors
+        }
+    })
+}
+
+fn cursor_monitor(monitors: &[Monitor]) -> Option<Monitor> {
+    let Mouse::Position { x, y } = Mouse::get_mouse_position() else {
+        warn!("unable to read the cursor position, use primary monitor");
+        return None;
+    };
+    monitors
+        .iter()
+        .find(|monitor| contains(*monitor.position(), *monitor.size(), x, y))
+        .cloned()
+}
+
+fn contains(position: PhysicalPosition<i32>, size: PhysicalSize<u32>, x: i32, y: i32) -> bool {
+    let right = position.x.saturating_add_unsigned(size.width);
+    let bottom = position.y.saturating_add_unsigned(size.height);
+    (position.x..right).contains(&x) && (position.y..bottom).contains(&y)
+}
+
+/// Bottom right corner of the monitor, a 16:9 widget of `size_percent` of the monitor width.
+fn corner(
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    size_percent: u32,
+) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    let width = size.width * size_percent / 100;
+    let height = width * 9 / 16;
+    let margin = size.height / CORNER_MARGIN_DIVISOR;
+    let x = position
+        .x
+        .saturating_add_unsigned(size.width.saturating_sub(width + margin));
+    let y = position
+        .y
+        .saturating_add_unsigned(size.height.saturating_sub(height + margin));
+    (
+        PhysicalPosition::new(x, y),
+        PhysicalSize::new(width, height),
+    )
+}
+
+fn move_to(
+    window: &WebviewWindow,
+    monitor: &Monitor,
+    layout: &OverlayLayout,
+) -> Result<(), anyhow::Error> {
+    debug!(
+        "place session window {} on monitor {:?} as {:?}",
+        window.label(),
+        monitor.name(),
+        layout.placement
+    );
+    window.unmaximize()?;
+    match layout.placement {
+        OverlayPlacement::FullScreen => {
+            window.set_position(*monitor.position())?;
+            window.set_size(*monitor.size())?;
+            window.maximize()?;
+        }
+        OverlayPlacement::Corner => {
+            let (position, size) =
+                corner(*monitor.position(), *monitor.size(), layout.size_percent);
+            window.set_position(position)?;
+            window.set_size(size)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        let position = PhysicalPosition::new(-1920, 0);
+        let size = PhysicalSize::new(1920, 1080);
+        assert!(contains(position, size, -1920, 0));
+        assert!(contains(position, size, -1, 1079));
+        assert!(!contains(position, size, 0, 500));
+        assert!(!contains(position, size, -100, 1080));
+    }
+
+    #[test]
+    fn test_corner() {
+        let (position, size) = corner(
+            PhysicalPosition::new(1920, 0),
+            PhysicalSize::new(1920, 1080),
+            25,
+        );
+        assert_eq!(size, PhysicalSize::new(480, 270));
+        assert_eq!(
+            position,
+            PhysicalPosition::new(1920 + 1920 - 480 - 27, 1080 - 270 - 27)
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::worker::Worker;
+use crate::{CountdownTimerState, SettingsManagerState};
+use log::{debug, info};
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Wry};
+
+#[cfg(target_os = "linux")]
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+const LOW_BATTERY_PERCENT: u8 = 20;
+const POWER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const NORMAL_TICK_SPEED: chrono::Duration = chrono::Duration::milliseconds(1000);
+const LOW_POWER_TICK_SPEED: chrono::Duration = chrono::Duration::milliseconds(5000);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerStatus {
+    pub on_battery: bool,
+    pub charge_percent: Option<u8>,
+}
+
+impl PowerStatus {
+    /// Reads the current power source. Systems without battery are always on AC.
+    #[cfg(target_os = "linux")]
+    pub fn read() -> Self {
+        let mut ac_online = false;
+        let mut has_battery = false;
+        let mut discharging = false;
+        let mut charge_percent = None;
+
+        if let Ok(supplies) = fs::read_dir(POWER_SUPPLY_PATH) {
+            for supply in supplies.flatten() {
+                let path = supply.path();
+                match read_value(&path, "type").as_deref() {
+                    Some("Mains") | Some("USB") => {
+                        ac_online |= read_value(&path, "online").as_deref() == Some("1");
+                    }
+                    Some("Battery") => {
+                        // wireless mice and keyboards report their battery with scope "Device"
+                        if read_value(&path, "scope").as_deref() == Some("Device") {
+                            continue;
+                        }
+                        has_battery = true;
+                        discharging |=
+                            read_value(&path, "status").as_deref() == Some("Discharging");
+                        let capacity =
+                            read_value(&path, "capacity").and_then(|c| c.parse::<u8>().ok());
+                        if let Some(capacity) = capacity {
+                            charge_percent =
+                                Some(charge_percent.map_or(capacity, |c: u8| c.min(capacity)));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        PowerStatus {
+            on_battery: has_battery && (discharging || !ac_online),
+            charge_percent,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn read() -> Self {
+        PowerStatus {
+            on_battery: false,
+            charge_percent: None,
+        }
+    }
+
+    pub fn is_low_battery(&self) -> bool {
+        self.on_battery
+            && self
+                .charge_percent
+                .map(|charge| charge <= LOW_BATTERY_PERCENT)
+                .unwrap_or(false)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_value(path: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(path.join(name))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Returns true if background work should be reduced, based on the user settings.
+pub fn is_low_power(app: &AppHandle<Wry>) -> bool {
+    let reduce_on_low_battery = app
+        .state::<SettingsManagerState>()
+        .get_settings()
+        .map(|s| s.user.reduce_work_on_low_battery)
+        .unwrap_or(false);
+    reduce_on_low_battery && PowerStatus::read().is_low_battery()
+}
+
+/// Returns true if reminders should use a native notification instead of the overlay.
+pub fn prefer_notification(app: &AppHandle<Wry>) -> bool {
+    let notification_on_battery = app
+        .state::<SettingsManagerState>()
+        .get_settings()
+        .map(|s| s.user.notification_on_battery)
+        .unwrap_or(false);
+    notification_on_battery && PowerStatus::read().on_battery
+}
+
+/// Watches the power source and slows down the timer ticks while in low power.
+pub struct PowerMonitor {
+    worker: Worker,
+}
+
+impl PowerMonitor {
+    pub fn start(app: &AppHandle<Wry>) -> Result<Self, anyhow::Error> {
+        let app_handle = app.app_handle().clone();
+        let mut low_power = false;
+        let worker = Worker::start("power-monitor", POWER_CHECK_INTERVAL, move || {
+            let current = is_low_power(&app_handle);
+            if current != low_power {
+                info!("low power mode changed to {current}");
+                let tick_speed = if current {
+                    LOW_POWER_TICK_SPEED
+                } else {
+                    NORMAL_TICK_SPEED
+                };
+                app_handle
+                    .state::<CountdownTimerState>()
+                    .set_tick_speed(tick_speed);
+                low_power = current;
+            } else {
+                debug!("power status unchanged, low power: {low_power}");
+            }
+        })?;
+        Ok(PowerMonitor { worker })
+    }
+
+    /// Stops watching the power source, waits for the background thread to finish.
+    pub fn stop(&self) {
+        self.worker.stop();
+    }
+}
@@ -1,25 +1,38 @@
 use crate::alert::Alert;
-use crate::model::settings::WelcomeWizardMode;
+use crate::model::settings::{SettingsUserDetails, WelcomeWizardMode};
 use crate::{
-    countdown_timer, feedback_window, tracking, updater_window, welcome_window,
-    CountdownTimerState, LicenseManagerState, SettingsManagerState, SettingsSystemState,
-    TrackingState,
+    cli, countdown_timer, detect_idling, escalation, feedback_window, instance_control,
+    notification, overlay_monitors, power, tracking, updater_window, welcome_window,
+    CountdownTimerState, EscalationState, LicenseManagerState, SettingsManagerState,
+    SettingsSystemState, TrackingState,
 };
 use anyhow::{anyhow, Error};
+use chrono::{Local, Timelike};
 use core::clone::Clone;
-use log::info;
-use tauri::{AppHandle, EventId, Manager, State, WebviewWindowBuilder, Wry};
+use log::{info, warn};
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, EventId, Manager, State, WebviewWindow, WebviewWindowBuilder, Wry};
 use tauri_specta::Event;
 
 use crate::feedback_window::FeedbackDisplay;
 use crate::model::event::SessionStartEvent;
-use crate::model::session::{DrinkCharacter, SipSize};
+use crate::model::session::{
+    DrinkCharacter, ReminderSound, ReminderStyle, SessionOutcome, SipSize,
+};
+use crate::model::settings::{
+    default_overlay_layout, default_reminder_sound, default_snooze_minutes, default_sound_volume,
+};
+use crate::settings_validation::{check_sound_file, SOUND_VOLUME};
 
 pub const WINDOW_LABEL: &'static str = "session";
 
 pub fn init(app: &AppHandle<Wry>) -> Result<EventId, anyhow::Error> {
     let app_handle = app.clone();
-    build_session_window(app)?;
+    if !cli::is_headless(app) {
+        build_session_window(app, WINDOW_LABEL)?;
+        overlay_monitors::init(app)?;
+    }
     let id = countdown_timer::CountdownEvent::listen(app, move |status| {
         if status.payload.status == countdown_timer::TimerStatus::Finished {
             let app_handle_start = app_handle.clone();
@@ -27,9 +40,23 @@ pub fn init(app: &AppHandle<Wry>) -> Result<EventId, anyhow::Error> {
                 let timer = app_handle_start.app_handle().state::<CountdownTimerState>();
                 timer.restart();
 
-                show_session(&app_handle_start.app_handle(), None)
+                let settings = app_handle_start
+                    .state::<SettingsManagerState>()
+                    .get_settings();
+                if let Some(settings) = &settings {
+                    detect_idling::wait_for_typing_pause(&app_handle_start, &settings.user).await;
+                }
+
+                show_session(&app_handle_start.app_handle(), None, true)
                     .await
-                    .unwrap();
+                    .unwrap_or_else(|err| {
+                        app_handle_start.alert(
+                            "Can't start session",
+                            "There was an error while trying to start the session.",
+                            Some(err),
+                            false,
+                        );
+                    });
             });
         }
     });
@@ -43,12 +70,15 @@ pub async fn start_session(
     drink_settings: Option<SessionStartEvent>,
     timer: State<'_, CountdownTimerState>,
 ) -> Result<(), ()> {
-    let demo_mode = drink_settings.as_ref().map(|s| s.demo_mode).unwrap_or(false);
+    let demo_mode = drink_settings
+        .as_ref()
+        .map(|s| s.demo_mode)
+        .unwrap_or(false);
     if !demo_mode {
         timer.restart();
     }
 
-    show_session(&app, drink_settings)
+    show_session(&app, drink_settings, false)
         .await
         .unwrap_or_else(|err| {
             app.alert(
@@ -61,9 +91,12 @@ pub async fn start_session(
     Ok(())
 }
 
+/// Starts a reminder session. Only reminders of the timer `escalate`, a session the user
+/// asked for in the tray, on the command line or through an integration needs no follow-ups.
 pub async fn show_session(
     app: &AppHandle<Wry>,
     overwrite_settings: Option<SessionStartEvent>,
+    escalate: bool,
 ) -> Result<(), anyhow::Error> {
     let license_active = app
         .state::<LicenseManagerState>()
@@ -94,7 +127,7 @@ pub async fn show_session(
                 let mut settings_system = settings_system
                     .lock()
                     .map_err(|e| anyhow::anyhow!(e.to_string()))?;
-                settings_system.increase_session_count(&app);
+                settings_system.increase_session_count();
             }
 
             // send tracking event
@@ -109,37 +142,28 @@ pub async fn show_session(
             .get_settings()
             .map(|s| s.user);
 
-        let session_start: SessionStartEvent = overwrite_settings
-            .or_else(|| {
-                // Attempt to get from user settings if overwrite_settings is None
-                user_settings
-                    .as_ref()
-                    .map(|user_settings| SessionStartEvent {
-                        sip_size: user_settings.sip_size.clone(),
-                        selected_drink_character: user_settings.character.clone(),
-                        demo_mode: false,
-                    })
-            })
-            .unwrap_or_else(|| {
-                // Provide a default SessionStartEvent if both overwrite_settings and user settings are None
-                SessionStartEvent {
-                    sip_size: SipSize::BigSip,
-                    selected_drink_character: DrinkCharacter::YoungWoman,
-                    demo_mode: false,
-                }
-            });
+        let session_start = session_start_event(user_settings.as_ref(), overwrite_settings);
 
-        if let Some(_window) = app.get_webview_window(WINDOW_LABEL) {
-            info!("start session window: send event");
-            session_start.emit(app.app_handle())?;
-        } else {
-            app.alert(
-                "Session Window Missing",
-                "I am sorry, this should not happen. Please contact Rocket Solutions",
-                None,
-                false,
-            );
+        match user_settings {
+            Some(user) if escalate && !demo_mode && user.escalate_reminders => {
+                info!("start session: escalate until answered");
+                escalation::start(app, &user)?;
+            }
+            Some(user)
+                if cli::is_headless(app)
+                    || (!demo_mode
+                        && (user.reminder_style == ReminderStyle::Notification
+                            || power::prefer_notification(app))) =>
+            {
+                info!("start session: show notification");
+                notification::show_reminder(app, &user)?;
+            }
+            _ => show_overlay(app, session_start)?,
         }
+    } else if cli::is_headless(app) {
+        return Err(anyhow!(
+            "no active license, start Just Drink! without --headless to renew it"
+        ));
     } else {
         welcome_window::show(
             app.app_handle(),
@@ -152,27 +176,155 @@ pub async fn show_session(
     Ok(())
 }
 
-fn build_session_window(app: &AppHandle) -> Result<(), Error> {
-    info!("start session window: create new window");
-    let window =
-        WebviewWindowBuilder::new(app, WINDOW_LABEL, tauri::WebviewUrl::App("/session".into()))
-            .title("Just Drink! Session")
-            .transparent(true)
-            .visible(false)
-            .always_on_top(true)
-            .decorations(false)
-            .maximized(true)
-            .skip_taskbar(false)
-            .accept_first_mouse(true)
-            .visible_on_all_workspaces(true)
-            .focused(false)
-            .resizable(false);
+fn session_start_event(
+    user_settings: Option<&SettingsUserDetails>,
+    overwrite_settings: Option<SessionStartEvent>,
+) -> SessionStartEvent {
+    session_start_event_at(user_settings, overwrite_settings, minute_of_day())
+}
+
+fn session_start_event_at(
+    user_settings: Option<&SettingsUserDetails>,
+    overwrite_settings: Option<SessionStartEvent>,
+    minute: u32,
+) -> SessionStartEvent {
+    overwrite_settings
+        .or_else(|| {
+            // Attempt to get from user settings if overwrite_settings is None
+            user_settings.map(|user_settings| SessionStartEvent {
+                sip_size: user_settings.sip_size.clone(),
+                selected_drink_character: user_settings.character.clone(),
+                demo_mode: false,
+                muted: user_settings.is_quiet(minute),
+                sound: reminder_sound(user_settings),
+                volume: user_settings.sound_volume,
+                layout: user_settings.overlay_layout.clone(),
+            })
+        })
+        .unwrap_or_else(|| {
+            // Provide a default SessionStartEvent if both overwrite_settings and user settings are None
+            SessionStartEvent {
+                sip_size: SipSize::BigSip,
+                selected_drink_character: DrinkCharacter::YoungWoman,
+                demo_mode: false,
+                muted: false,
+                sound: default_reminder_sound(),
+                volume: default_sound_volume(),
+                layout: default_overlay_layout(),
+            }
+        })
+}
+
+/// The sound of the user, or the default sound if the file is gone or broken meanwhile.
+fn reminder_sound(user: &SettingsUserDetails) -> ReminderSound {
+    if let ReminderSound::File(path) = &user.reminder_sound {
+        if let Err(err) = check_sound_file(Path::new(path)) {
+            warn!("unable to play reminder sound, use default sound: {}", err);
+            return default_reminder_sound();
+        }
+    }
+    user.reminder_sound.clone()
+}
+
+fn minute_of_day() -> u32 {
+    let now = Local::now();
+    now.hour() * 60 + now.minute()
+}
+
+fn show_overlay(app: &AppHandle, session_start: SessionStartEvent) -> Result<(), anyhow::Error> {
+    if let Some(_window) = app.get_webview_window(WINDOW_LABEL) {
+        // the cursor may have moved to another monitor since the last reminder
+        overlay_monitors::arrange(app, &session_start.layout)?;
+        info!("start session window: send event");
+        session_start.emit(app.app_handle())?;
+    } else {
+        app.alert(
+            "Session Window Missing",
+            "I am sorry, this should not happen. Please contact Rocket Solutions",
+            None,
+            false,
+        );
+    }
+    Ok(())
+}
+
+/// Shows the overlay of an escalating reminder, without counting another session.
+pub fn show_escalated_overlay(
+    app: &AppHandle,
+    user: &SettingsUserDetails,
+    muted: bool,
+) -> Result<(), anyhow::Error> {
+    let mut session_start = session_start_event(Some(user), None);
+    session_start.muted |= muted;
+    show_overlay(app, session_start)
+}
+
+/// Plays the sound in a demo session, after checking a sound file of the user.
+#[specta::specta]
+#[tauri::command]
+pub async fn preview_sound(
+    app: AppHandle,
+    sound: ReminderSound,
+    volume: u32,
+) -> Result<(), String> {
+    if let ReminderSound::File(path) = &sound {
+        check_sound_file(Path::new(path))?;
+    }
+    if !SOUND_VOLUME.contains(&volume) {
+        return Err(format!("volume {} is not between 0 and 100", volume));
+    }
+
+    let user = app
+        .state::<SettingsManagerState>()
+        .get_settings()
+        .map(|s| s.user);
+    let session_start = SessionStartEvent {
+        demo_mode: true,
+        muted: false,
+        sound,
+        volume,
+        ..session_start_event(user.as_ref(), None)
+    };
+    show_session(&app, Some(session_start), false)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Label of the session window on the monitor at `index`, further monitors show mirrors.
+pub fn window_label(index: usize) -> String {
+    match index {
+        0 => WINDOW_LABEL.to_string(),
+        _ => format!("{WINDOW_LABEL}-{index}"),
+    }
+}
+
+/// True for the label of a session window on a further monitor, it plays no sound.
+pub fn is_mirror(label: &str) -> bool {
+    label
+        .strip_prefix(WINDOW_LABEL)
+        .is_some_and(|index| index.starts_with('-'))
+}
+
+pub(crate) fn build_session_window(app: &AppHandle, label: &str) -> Result<WebviewWindow, Error> {
+    info!("start session window: create new window {label}");
+    let window = WebviewWindowBuilder::new(app, label, tauri::WebviewUrl::App("/session".into()))
+        .title("Just Drink! Session")
+        .transparent(true)
+        .visible(false)
+        .always_on_top(true)
+        .decorations(false)
+        .maximized(true)
+        .skip_taskbar(false)
+        .accept_first_mouse(true)
+        .visible_on_all_workspaces(true)
+        .focused(false)
+        .resizable(false);
 
     info!("start session window: build");
     let window = window.build()?;
     window.set_ignore_cursor_events(true)?;
 
-    Ok(())
+    Ok(window)
 }
 
 pub(crate) fn days_between(
@@ -183,39 +335,116 @@ pub(crate) fn days_between(
     duration.num_days() + 1
 }
 
+/// Ends the session, `outcome` is the answer of an interactive overlay. An overlay
+/// nobody answered ends without one.
 #[specta::specta]
 #[tauri::command]
 pub async fn end_session(
     app: AppHandle,
-    settings_system: State<'_, SettingsSystemState>,
     demo_mode: bool,
+    outcome: Option<SessionOutcome>,
 ) -> Result<(), String> {
-    info!("end reminder session");
-    hide_window(&app)?;
+    finish_session(&app, outcome, demo_mode)
+        .await
+        .map_err(|err| err.to_string())
+}
 
-    if !demo_mode {
-        let ask_for_feedback = {
-            let ss = settings_system
-                .lock()
-                .expect("settings_system should not be locked");
-            ss.should_show_feedback()
-        };
+/// Amount to log for the answer to a reminder. Quiet hours only mute the overlay, a
+/// confirmed drink is logged all the same.
+fn intake_ml(outcome: Option<&SessionOutcome>, user: &SettingsUserDetails) -> Option<u32> {
+    (outcome == Some(&SessionOutcome::Drank)).then(|| user.sip_size.ml())
+}
 
-        let updater_visible = updater_window::show_if_update_available(&app, false, false).await;
+/// Ends the reminder session with the answer from the overlay or the notification, only
+/// a confirmed drink is logged.
+pub async fn finish_session(
+    app: &AppHandle,
+    outcome: Option<SessionOutcome>,
+    demo_mode: bool,
+) -> Result<(), anyhow::Error> {
+    info!("end reminder session: {:?}", outcome);
+    hide_window(app).map_err(|err| anyhow!(err))?;
+    if !cli::is_headless(app) {
+        // mirrors of monitors removed during the reminder close now, not with the next one
+        overlay_monitors::place(app)
+            .unwrap_or_else(|err| warn!("unable to place session windows: {err}"));
+    }
+    if demo_mode {
+        return Ok(());
+    }
+    if outcome.is_some() {
+        if let Some(escalation) = app.try_state::<EscalationState>() {
+            escalation.stop();
+        }
+    }
 
-        if ask_for_feedback && !updater_visible {
-            feedback_window::show(&app).expect("unable to show feedback window");
+    let user = app
+        .state::<SettingsManagerState>()
+        .get_settings()
+        .map(|s| s.user);
+    if let Some(ml) = user
+        .as_ref()
+        .and_then(|user| intake_ml(outcome.as_ref(), user))
+    {
+        instance_control::log_intake(app, ml)?;
+    }
+    match outcome {
+        Some(SessionOutcome::Snooze) => {
+            let minutes = user.map_or_else(default_snooze_minutes, |user| user.snooze_minutes);
+            info!("snooze reminder for {} minutes", minutes);
+            app.state::<CountdownTimerState>()
+                .start(Duration::from_secs(u64::from(minutes) * 60));
         }
+        Some(SessionOutcome::Drank) | Some(SessionOutcome::Skip) | None => {}
+    }
+
+    if cli::is_headless(app) {
+        return Ok(());
+    }
+    let ask_for_feedback = {
+        let ss = app.state::<SettingsSystemState>();
+        let ss = ss.lock().expect("settings_system should not be locked");
+        ss.should_show_feedback()
+    };
+
+    let updater_visible = updater_window::show_if_update_available(app, false, false).await;
+
+    if ask_for_feedback && !updater_visible {
+        feedback_window::show(app)?;
     }
 
     Ok(())
 }
 
 pub fn hide_window(app: &AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
-        window
-            .hide()
-            .map_err(|err| format!("window can't be closed: {}", err))?;
+    for (label, window) in app.webview_windows() {
+        if label == WINDOW_LABEL || is_mirror(&label) {
+            window
+                .hide()
+                .map_err(|err| format!("window can't be closed: {}", err))?;
+        }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings_manager::UserSettingsStore;
+
+    #[test]
+    fn test_quiet_hours_session_logs_drank() {
+        let mut user = UserSettingsStore::default().user;
+        user.mute_during_quiet_hours = true;
+        user.quiet_hours_start_minute = 22 * 60;
+        user.quiet_hours_end_minute = 7 * 60;
+
+        assert!(session_start_event_at(Some(&user), None, 23 * 60).muted);
+        assert_eq!(
+            intake_ml(Some(&SessionOutcome::Drank), &user),
+            Some(user.sip_size.ml())
+        );
+        assert_eq!(intake_ml(Some(&SessionOutcome::Skip), &user), None);
+        assert_eq!(intake_ml(None, &user), None);
+    }
+}
@@ -0,0 +1,466 @@
+use crate::model::backup::{SettingsChange, SettingsImportPreview};
+use crate::settings_manager::{SettingsManager, UserSettingsStore};
+use crate::settings_validation::Validate;
+use crate::{cli, settings_migration, SettingsManagerState, SettingsSystemState};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+const EXPORT_FORMAT: &str = "justdrink-settings";
+const EXPORT_FORMAT_VERSION: u32 = 1;
+const BACKUP_PREFIX: &str = "mm-config.backup";
+/// Backups of the settings replaced by an import, older ones are deleted.
+const BACKUPS_KEPT: usize = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SettingsExport {
+    format: String,
+    format_version: u32,
+    app_version: String,
+    exported_at: DateTime<Utc>,
+    settings: Value,
+    system: ExportedSystemSettings,
+}
+
+/// Machine independent part of the `SettingsSystemDetails`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ExportedSystemSettings {
+    feedback_provided: bool,
+}
+
+impl SettingsExport {
+    fn new(
+        settings: UserSettingsStore,
+        feedback_provided: bool,
+        app_version: String,
+    ) -> Result<Self, anyhow::Error> {
+        Ok(SettingsExport {
+            format: EXPORT_FORMAT.to_string(),
+            format_version: EXPORT_FORMAT_VERSION,
+            app_version,
+            exported_at: Utc::now(),
+            settings: serde_json::to_value(settings)?,
+            system: ExportedSystemSettings { feedback_provided },
+        })
+    }
+}
+
+struct SettingsImport {
+    export: SettingsExport,
+    settings: UserSettingsStore,
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn settings_export(app: AppHandle, path: String) -> Result<(), String> {
+    export(&app, Path::new(&path)).map_err(|err| format!("unable to export settings: {}", err))
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn settings_import_preview(
+    path: String,
+    settings_manager: State<'_, SettingsManagerState>,
+) -> Result<SettingsImportPreview, String> {
+    let import =
+        read_import(Path::new(&path)).map_err(|err| format!("invalid settings file: {}", err))?;
+    preview(&settings_manager, &import)
+        .map_err(|err| format!("unable to compare settings: {}", err))
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn settings_import(
+    app: AppHandle,
+    path: String,
+    settings_manager: State<'_, SettingsManagerState>,
+    settings_system: State<'_, SettingsSystemState>,
+) -> Result<(), String> {
+    let import =
+        read_import(Path::new(&path)).map_err(|err| format!("invalid settings file: {}", err))?;
+
+    if let Some(backup) =
+        backup_current(&app).map_err(|err| format!("unable to backup settings: {}", err))?
+    {
+        info!("previous settings saved to {:?}", backup);
+    }
+
+    settings_manager
+        .import(import.settings)
+        .map_err(|err| format!("unable to save imported settings: {}", err))?;
+
+    if import.export.system.feedback_provided {
+        settings_system
+            .lock()
+            .map_err(|err| err.to_string())?
+            .feedback_given();
+    }
+
+    info!("imported settings from {}", path);
+    Ok(())
+}
+
+fn export(app: &AppHandle, path: &Path) -> Result<(), anyhow::Error> {
+    let settings = app
+        .state::<SettingsManagerState>()
+        .get_stored_settings()
+        .ok_or_else(|| anyhow!("Just Drink! is not configured yet"))?;
+
+    let feedback_provided = app
+        .state::<SettingsSystemState>()
+        .lock()
+        .map_err(|err| anyhow!(err.to_string()))?
+        .settings()
+        .feedback_provided;
+
+    let app_version = app
+        .config()
+        .version
+        .clone()
+        .unwrap_or("unknown".to_string());
+    let export = SettingsExport::new(settings, feedback_provided, app_version)?;
+
+    fs::write(path, serde_json::to_string_pretty(&export)?)?;
+    info!("exported settings to {:?}", path);
+    Ok(())
+}
+
+fn read_import(path: &Path) -> Result<SettingsImport, anyhow::Error> {
+    parse_import(&fs::read_to_string(path)?)
+}
+
+fn parse_import(content: &str) -> Result<SettingsImport, anyhow::Error> {
+    let export: SettingsExport = serde_json::from_str(content)?;
+
+    if export.format != EXPORT_FORMAT {
+        return Err(anyhow!("not a Just Drink! settings file"));
+    }
+    if export.format_version > EXPORT_FORMAT_VERSION {
+        return Err(anyhow!(
+            "settings file was exported by a newer version ({})",
+            export.app_version
+        ));
+    }
+
+    // exports of older versions use the schema of their time
+    let migrated = settings_migration::migrate(export.settings.clone())?;
+    if migrated.is_newer() {
+        return Err(anyhow!(
+            "settings file was exported by a newer version ({})",
+            export.app_version
+        ));
+    }
+    let settings: UserSettingsStore = serde_json::from_value(migrated.data)?;
+    settings.user.validate()?;
+    for profile in &settings.profiles {
+        profile.user.validate()?;
+        if let Some(schedule) = &profile.schedule {
+            schedule.validate()?;
+        }
+    }
+
+    Ok(SettingsImport { export, settings })
+}
+
+/// Changes of the import compared to the saved settings, command line overrides and the
+/// policy are not part of the file and would show up as changes otherwise. The import
+/// replaces the profiles as well, so their changes are listed after the active settings.
+fn preview(
+    settings_manager: &SettingsManager,
+    import: &SettingsImport,
+) -> Result<SettingsImportPreview, anyhow::Error> {
+    let stored = settings_manager.get_stored_settings();
+    let mut changes = diff("", stored.as_ref().map(|s| &s.user), &import.settings.user)?;
+    changes.extend(profile_changes(stored.as_ref(), &import.settings)?);
+    Ok(SettingsImportPreview {
+        exported_by_version: import.export.app_version.clone(),
+        exported_at: import.export.exported_at.to_rfc3339(),
+        changes,
+    })
+}
+
+/// Active profile and the profiles added, removed or changed by the import.
+fn profile_changes(
+    current: Option<&UserSettingsStore>,
+    imported: &UserSettingsStore,
+) -> Result<Vec<SettingsChange>, anyhow::Error> {
+    let mut changes = Vec::new();
+    let current_active = current.map(|c| Value::from(c.active_profile.as_str()));
+    let imported_active = Value::from(imported.active_profile.as_str());
+    if current_active.as_ref() != Some(&imported_active) {
+        changes.push(SettingsChange {
+            field: "active_profile".to_string(),
+            current: current_active.map(|value| value.to_string()),
+            imported: Some(imported_active.to_string()),
+        });
+    }
+
+    let current_profiles = current.map(|c| c.profiles.as_slice()).unwrap_or_default();
+    for profile in &imported.profiles {
+        let field = format!("profiles.{}", profile.name);
+        match current_profiles.iter().find(|p| p.name == profile.name) {
+            None => changes.push(SettingsChange {
+                field,
+                current: None,
+                imported: Some(serde_json::to_string(profile)?),
+            }),
+            Some(current_profile) => {
+                changes.extend(diff(
+                    &format!("{}.", field),
+                    Some(&current_profile.user),
+                    &profile.user,
+                )?);
+                let current_schedule = serde_json::to_value(&current_profile.schedule)?;
+                let imported_schedule = serde_json::to_value(&profile.schedule)?;
+                if current_schedule != imported_schedule {
+                    changes.push(SettingsChange {
+                        field: format!("{}.schedule", field),
+                        current: Some(current_schedule.to_string()),
+                        imported: Some(imported_schedule.to_string()),
+                    });
+                }
+            }
+        }
+    }
+    for profile in current_profiles {
+        if !imported.profiles.iter().any(|p| p.name == profile.name) {
+            changes.push(SettingsChange {
+                field: format!("profiles.{}", profile.name),
+                current: Some(serde_json::to_string(profile)?),
+                imported: None,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+fn diff<T: Serialize>(
+    prefix: &str,
+    current: Option<&T>,
+    imported: &T,
+) -> Result<Vec<SettingsChange>, anyhow::Error> {
+    let current = current.map(serde_json::to_value).transpose()?;
+    let imported = serde_json::to_value(imported)?;
+
+    let changes = imported
+        .as_object()
+        .ok_or_else(|| anyhow!("settings are not a json object"))?
+        .iter()
+        .filter_map(|(field, imported_value)| {
+            let current_value = current.as_ref().and_then(|c| c.get(field));
+            if current_value == Some(imported_value) {
+                None
+            } else {
+                Some(SettingsChange {
+                    field: format!("{}{}", prefix, field),
+                    current: current_value.map(|value| value.to_string()),
+                    imported: Some(imported_value.to_string()),
+                })
+            }
+        })
+        .collect();
+
+    Ok(changes)
+}
+
+/// Writes the current user settings next to the store, before they are overwritten.
+fn backup_current(app: &AppHandle) -> Result<Option<PathBuf>, anyhow::Error> {
+    let Some(settings) = app.state::<SettingsManagerState>().get_stored_settings() else {
+        return Ok(None);
+    };
+
+    let dir = cli::config_dir(app)?;
+    let backup = write_backup(&dir, &settings)?;
+    prune_backups(&dir, BACKUPS_KEPT)?;
+    Ok(Some(backup))
+}
+
+/// Writes a backup without ever replacing an existing one, imports within the same
+/// millisecond get a numbered name.
+fn write_backup(dir: &Path, settings: &UserSettingsStore) -> Result<PathBuf, anyhow::Error> {
+    let content = serde_json::to_string_pretty(settings)?;
+    let stamp = Utc::now().format("%Y%m%d-%H%M%S-%3f");
+    for attempt in 0.. {
+        let name = match attempt {
+            0 => format!("{}-{}.json", BACKUP_PREFIX, stamp),
+            n => format!("{}-{}-{}.json", BACKUP_PREFIX, stamp, n),
+        };
+        let path = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(content.as_bytes())?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    unreachable!("attempts are unbounded")
+}
+
+/// Deletes all but the newest backups, their names sort by the time of the import.
+fn prune_backups(dir: &Path, keep: usize) -> Result<(), anyhow::Error> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(BACKUP_PREFIX) && name.ends_with(".json"))
+        })
+        .collect();
+    // without the extension a numbered name sorts after the first of the same millisecond
+    backups.sort_by(|a, b| a.file_stem().cmp(&b.file_stem()));
+    let outdated = backups.len().saturating_sub(keep);
+    for backup in &backups[..outdated] {
+        info!("remove old settings backup {:?}", backup);
+        fs::remove_file(backup)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings_overrides::SettingsOverrides;
+    use crate::settings_storage::{test_manager, MemoryStorage, SettingsStorage};
+    use std::sync::Arc;
+
+    fn export_of(settings: UserSettingsStore) -> String {
+        let export = SettingsExport::new(settings, false, "1.1.0".to_string()).unwrap();
+        serde_json::to_string_pretty(&export).unwrap()
+    }
+
+    fn import_of(settings: UserSettingsStore) -> SettingsImport {
+        parse_import(&export_of(settings)).unwrap()
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let storage = Arc::new(MemoryStorage::default());
+        let sm = test_manager(&storage, SettingsOverrides::default());
+        let mut user = UserSettingsStore::default().user;
+        user.drink_amount_ml = 2000;
+        sm.update_user(user.clone()).unwrap();
+        sm.save_profile("Focus", None).unwrap();
+        sm.switch_profile("Focus").unwrap();
+        user.next_break_duration_minutes = 45;
+        sm.update_user(user).unwrap();
+
+        let imported_storage = Arc::new(MemoryStorage::default());
+        let imported = test_manager(&imported_storage, SettingsOverrides::default());
+        let import = parse_import(&export_of(sm.get_stored_settings().unwrap())).unwrap();
+        imported.import(import.settings).unwrap();
+
+        assert_eq!(imported_storage.read().unwrap(), storage.read().unwrap());
+        let settings = imported.get_settings().unwrap();
+        assert_eq!(settings.active_profile, "Focus");
+        assert_eq!(settings.profile_names(), vec!["Default", "Focus"]);
+        assert_eq!(settings.user.next_break_duration_minutes, 45);
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        assert!(parse_import("{}").is_err());
+
+        let mut export: Value =
+            serde_json::from_str(&export_of(UserSettingsStore::default())).unwrap();
+        export["format"] = "other".into();
+        assert!(parse_import(&export.to_string()).is_err());
+
+        let mut settings = UserSettingsStore::default();
+        settings.profiles[0].user.drink_amount_ml = 0;
+        assert!(parse_import(&export_of(settings)).is_err());
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let mut export: Value =
+            serde_json::from_str(&export_of(UserSettingsStore::default())).unwrap();
+        export["settings"]["schema_version"] =
+            (settings_migration::CURRENT_SCHEMA_VERSION + 1).into();
+        assert!(parse_import(&export.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_preview_ignores_overrides() {
+        let storage = Arc::new(MemoryStorage::with_value(
+            serde_json::to_value(UserSettingsStore::default()).unwrap(),
+        ));
+        let overrides = SettingsOverrides {
+            next_break_duration_minutes: Some(5),
+            disable_idle_detection: true,
+            profile: None,
+        };
+        let sm = test_manager(&storage, overrides);
+
+        let mut imported = UserSettingsStore::default();
+        imported.user.drink_amount_ml = 2000;
+        let preview = preview(&sm, &import_of(imported)).unwrap();
+
+        assert_eq!(
+            preview.changes,
+            vec![SettingsChange {
+                field: "drink_amount_ml".to_string(),
+                current: Some("3000".to_string()),
+                imported: Some("2000".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_preview_lists_profile_changes() {
+        let storage = Arc::new(MemoryStorage::default());
+        let sm = test_manager(&storage, SettingsOverrides::default());
+        sm.use_defaults().unwrap();
+        sm.save_profile("Focus", None).unwrap();
+        sm.save_profile("Evening", None).unwrap();
+
+        let mut imported = sm.get_stored_settings().unwrap();
+        imported
+            .profiles
+            .retain(|profile| profile.name != "Evening");
+        imported.profiles[1].user.drink_amount_ml = 2000;
+        let preview = preview(&sm, &import_of(imported)).unwrap();
+
+        let fields: Vec<String> = preview
+            .changes
+            .iter()
+            .map(|change| change.field.clone())
+            .collect();
+        assert_eq!(
+            fields,
+            vec!["profiles.Focus.drink_amount_ml", "profiles.Evening"]
+        );
+        assert_eq!(preview.changes[1].imported, None);
+    }
+
+    #[test]
+    fn test_backups_are_kept_and_pruned() {
+        let dir = std::env::temp_dir().join(format!("justdrink-backup-{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let settings = UserSettingsStore::default();
+
+        let backups: Vec<PathBuf> = (0..BACKUPS_KEPT + 2)
+            .map(|_| write_backup(&dir, &settings).unwrap())
+            .collect();
+        assert_eq!(
+            backups
+                .iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            backups.len()
+        );
+
+        prune_backups(&dir, BACKUPS_KEPT).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), BACKUPS_KEPT);
+        assert!(backups.last().unwrap().exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
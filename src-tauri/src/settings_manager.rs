@@ -1,75 +1,258 @@
+use crate::alert::Alert;
+use crate::cli;
 use crate::model::session::{DrinkCharacter, GenderType, SipSize};
-use crate::model::settings::SettingsUserDetails;
-use anyhow::Result;
+use crate::model::settings::{
+    default_escalate_reminders, default_escalation_minutes, default_http_api_enabled,
+    default_http_api_port, default_mute_during_quiet_hours, default_notification_on_battery,
+    default_overlay_layout, default_overlay_monitor, default_pause_during_calls,
+    default_quiet_hours_end_minute, default_quiet_hours_start_minute,
+    default_reduce_work_on_low_battery, default_reminder_sound, default_reminder_style,
+    default_snooze_minutes, default_sound_volume, default_typing_pause_max_wait_seconds,
+    default_typing_pause_seconds, default_wait_for_typing_pause, ProfileSchedule,
+    SettingsChangedEvent, SettingsProfile, SettingsUserDetails,
+};
+use crate::settings_migration;
+use crate::settings_overrides::SettingsOverrides;
+use crate::settings_policy::SettingsPolicy;
+use crate::settings_storage::{self, FileStorage, Recovery, SettingsStorage};
+use anyhow::{anyhow, Result};
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Manager}; // For Tauri state management
-use tauri_plugin_store::{Store, StoreBuilder};
 
 const STORE_NAME: &str = "mm-config.json";
+const ROOT_PATH: &str = "data";
+pub const DEFAULT_PROFILE: &str = "Default";
+
+type SettingsListener = Box<dyn Fn(&SettingsChangedEvent) + Send + Sync>;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UserSettingsStore {
     pub version: String,
+    pub schema_version: u32,
+    /// Settings of the active profile.
     pub user: SettingsUserDetails,
+    pub active_profile: String,
+    pub profiles: Vec<SettingsProfile>,
 }
 
 impl Default for UserSettingsStore {
     fn default() -> Self {
+        let user = SettingsUserDetails {
+            character: DrinkCharacter::YoungMan,
+            sip_size: SipSize::FullCup,
+            gender_type: GenderType::Male,
+            consent: false,
+            next_break_duration_minutes: 15,
+            drink_amount_ml: 3000,
+            active: true,
+            beta_version: false,
+            enable_on_startup: true,
+            enable_idle_detection: true,
+            pause_during_calls: default_pause_during_calls(),
+            allow_tracking: true,
+            wait_for_typing_pause: default_wait_for_typing_pause(),
+            typing_pause_seconds: default_typing_pause_seconds(),
+            typing_pause_max_wait_seconds: default_typing_pause_max_wait_seconds(),
+            notification_on_battery: default_notification_on_battery(),
+            reduce_work_on_low_battery: default_reduce_work_on_low_battery(),
+            http_api_enabled: default_http_api_enabled(),
+            http_api_port: default_http_api_port(),
+            reminder_style: default_reminder_style(),
+            escalate_reminders: default_escalate_reminders(),
+            escalation_minutes: default_escalation_minutes(),
+            snooze_minutes: default_snooze_minutes(),
+            reminder_sound: default_reminder_sound(),
+            sound_volume: default_sound_volume(),
+            mute_during_quiet_hours: default_mute_during_quiet_hours(),
+            quiet_hours_start_minute: default_quiet_hours_start_minute(),
+            quiet_hours_end_minute: default_quiet_hours_end_minute(),
+            overlay_monitor: default_overlay_monitor(),
+            overlay_layout: default_overlay_layout(),
+        };
         UserSettingsStore {
             version: "0.0.0".to_string(),
-            user: SettingsUserDetails {
-                character: DrinkCharacter::YoungMan,
-                sip_size: SipSize::FullCup,
-                gender_type: GenderType::Male,
-                consent: false,
-                next_break_duration_minutes: 15,
-                drink_amount_ml: 3000,
-                active: true,
-                beta_version: false,
-                enable_on_startup: true,
-                enable_idle_detection: true,
-                allow_tracking: true,
-            },
+            schema_version: settings_migration::CURRENT_SCHEMA_VERSION,
+            active_profile: DEFAULT_PROFILE.to_string(),
+            profiles: vec![SettingsProfile {
+                name: DEFAULT_PROFILE.to_string(),
+                user: user.clone(),
+                schedule: None,
+            }],
+            user,
+        }
+    }
+}
+
+impl UserSettingsStore {
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.iter().map(|p| p.name.clone()).collect()
+    }
+
+    fn profile_mut(&mut self, name: &str) -> Option<&mut SettingsProfile> {
+        self.profiles.iter_mut().find(|p| p.name == name)
+    }
+
+    /// Copies the user settings into the active profile.
+    fn store_active_profile(&mut self) {
+        let user = self.user.clone();
+        let name = self.active_profile.clone();
+        match self.profile_mut(&name) {
+            Some(profile) => profile.user = user,
+            None => self.profiles.push(SettingsProfile {
+                name,
+                user,
+                schedule: None,
+            }),
+        }
+    }
+
+    fn update_profile_user(&mut self, name: &str, user: SettingsUserDetails) {
+        if name == self.active_profile {
+            self.user = user;
+            self.store_active_profile();
+        } else if let Some(profile) = self.profile_mut(name) {
+            profile.user = user;
+        }
+    }
+
+    fn switch_profile(&mut self, name: &str) -> Result<()> {
+        let user = self
+            .profiles
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.user.clone())
+            .ok_or_else(|| anyhow!("unknown profile '{}'", name))?;
+        self.user = user;
+        self.active_profile = name.to_string();
+        Ok(())
+    }
+
+    /// Updates the schedule of an existing profile, or creates a new profile from the
+    /// active settings.
+    fn save_profile(&mut self, name: &str, schedule: Option<ProfileSchedule>) {
+        let user = self.user.clone();
+        match self.profile_mut(name) {
+            Some(profile) => profile.schedule = schedule,
+            None => self.profiles.push(SettingsProfile {
+                name: name.to_string(),
+                user,
+                schedule,
+            }),
+        }
+    }
+
+    /// Overrides the active settings and all profiles with the values enforced by the policy.
+    fn enforce(&mut self, policy: &SettingsPolicy) -> Result<()> {
+        self.user = policy.apply(&self.user)?;
+        for profile in self.profiles.iter_mut() {
+            profile.user = policy.apply(&profile.user)?;
         }
+        Ok(())
+    }
+
+    fn delete_profile(&mut self, name: &str) -> Result<()> {
+        if name == self.active_profile {
+            return Err(anyhow!("the active profile can't be deleted"));
+        }
+        let count = self.profiles.len();
+        self.profiles.retain(|p| p.name != name);
+        if self.profiles.len() == count {
+            return Err(anyhow!("unknown profile '{}'", name));
+        }
+        Ok(())
     }
 }
 
 pub struct SettingsManager {
-    store: Arc<Store<tauri::Wry>>,
+    storage: Arc<dyn SettingsStorage>,
     version: String,
+    policy: SettingsPolicy,
+    overrides: Mutex<SettingsOverrides>,
     settings: Mutex<Option<UserSettingsStore>>,
+    listeners: Mutex<Vec<SettingsListener>>,
+    /// Settings of a newer schema are never saved, it would drop the fields unknown here.
+    read_only: AtomicBool,
 }
 
 impl SettingsManager {
     // Initialize the SettingsManager
-    pub fn new(app: &AppHandle) -> Result<Self> {
-        let store = StoreBuilder::new(app.app_handle(), STORE_NAME).build()?;
-        let settings = Mutex::new(None);
+    pub fn new(app: &AppHandle, overrides: SettingsOverrides) -> Result<Self> {
+        let storage = FileStorage::new(cli::config_path(app, STORE_NAME)?, ROOT_PATH);
         let version = app
             .config()
             .version
             .clone()
             .unwrap_or_else(|| "0.0.0".to_string());
-        let sm = Self {
-            store,
-            version,
-            settings,
-        };
-        sm.load()
-            .unwrap_or_else(|err| warn!("unable to read configuration, err: {:?}", err));
+        let policy = SettingsPolicy::load().unwrap_or_else(|err| {
+            app.alert(
+                "Invalid settings policy",
+                "The settings policy of your administrator can't be read, it is ignored.",
+                Some(err),
+                false,
+            );
+            SettingsPolicy::default()
+        });
+        let sm = Self::with_storage(Arc::new(storage), version, policy, overrides);
+        match sm.load() {
+            Ok(Some(recovery)) => app.alert(
+                "Settings restored",
+                &format!(
+                    "Your settings were damaged and have been restored from the last backup. The damaged file was kept at {}.",
+                    recovery
+                        .damaged_copy
+                        .map(|path| path.display().to_string())
+                        .unwrap_or_default()
+                ),
+                Some(recovery.error),
+                false,
+            ),
+            Ok(None) => {}
+            Err(err) => app.alert(
+                "Settings lost",
+                "Your settings were damaged and could not be restored, please set up Just Drink! again.",
+                Some(err),
+                false,
+            ),
+        }
         Ok(sm)
     }
 
-    // Load settings from the store
-    pub fn load(&self) -> Result<()> {
-        let data_json = self
-            .store
-            .get("data".to_string())
-            .ok_or_else(|| anyhow::anyhow!("Can't find settings in data"))?;
+    /// Creates a manager independent of a running app, call `load` to read the settings.
+    pub fn with_storage(
+        storage: Arc<dyn SettingsStorage>,
+        version: String,
+        policy: SettingsPolicy,
+        overrides: SettingsOverrides,
+    ) -> Self {
+        SettingsManager {
+            storage,
+            version,
+            policy,
+            overrides: Mutex::new(overrides),
+            settings: Mutex::new(None),
+            listeners: Mutex::new(Vec::new()),
+            read_only: AtomicBool::new(false),
+        }
+    }
+
+    /// Loads the settings, returns how they were recovered if the file was damaged.
+    pub fn load(&self) -> Result<Option<Recovery>> {
+        let Some(loaded) = settings_storage::load(self.storage.as_ref(), |data| {
+            let migrated = settings_migration::migrate(data)?;
+            let user_settings: UserSettingsStore = serde_json::from_value(migrated.data.clone())?;
+            Ok((migrated, user_settings))
+        })?
+        else {
+            info!("no settings found in {}", self.storage.describe());
+            return Ok(None);
+        };
 
-        let user_settings: UserSettingsStore = serde_json::from_value(data_json.clone())?;
+        let (migrated, mut user_settings) = loaded.value;
+        user_settings.enforce(&self.policy)?;
+        self.read_only.store(migrated.is_newer(), Ordering::Relaxed);
 
         {
             let mut settings_guard = self.settings.lock().map_err(|e| {
@@ -77,34 +260,166 @@ impl SettingsManager {
             })?;
             *settings_guard = Some(user_settings.clone());
         }
-        Ok(())
+
+        if migrated.was_migrated() {
+            info!(
+                "settings migrated from schema {} to {}",
+                migrated.from_version, user_settings.schema_version
+            );
+            self.save()?;
+        }
+        Ok(loaded.recovery)
+    }
+
+    /// Registers a listener, called after every update which changed at least one field.
+    pub fn subscribe(&self, listener: impl Fn(&SettingsChangedEvent) + Send + Sync + 'static) {
+        self.listeners
+            .lock()
+            .expect("settings listeners - should not be poisoned")
+            .push(Box::new(listener));
     }
 
     pub fn update_user(&self, user_settings: SettingsUserDetails) -> Result<()> {
-        {
+        self.modify(|store, overrides| {
+            let mut user_settings = user_settings;
+            let target = overrides.target_profile(store);
+            if let Some(stored) = store.profiles.iter().find(|p| p.name == target) {
+                overrides.restore(&stored.user, &mut user_settings);
+            }
+            store.update_profile_user(&target, user_settings);
+            Ok(())
+        })
+    }
+
+    /// Replaces all settings, including the profiles, with the imported ones.
+    pub fn import(&self, imported: UserSettingsStore) -> Result<()> {
+        self.modify(|store, _| {
+            *store = UserSettingsStore {
+                version: store.version.clone(),
+                ..imported
+            };
+            Ok(())
+        })
+    }
+
+    /// Activates the profile, listeners reconfigure themselves like on any settings change.
+    pub fn switch_profile(&self, name: &str) -> Result<()> {
+        self.modify(|store, overrides| {
+            // an explicit choice replaces the profile given on the command line
+            overrides.profile = None;
+            store.switch_profile(name)
+        })
+    }
+
+    pub fn save_profile(&self, name: &str, schedule: Option<ProfileSchedule>) -> Result<()> {
+        self.modify(|store, _| {
+            store.save_profile(name, schedule);
+            Ok(())
+        })
+    }
+
+    pub fn delete_profile(&self, name: &str) -> Result<()> {
+        self.modify(|store, _| store.delete_profile(name))
+    }
+
+    fn modify(
+        &self,
+        change: impl FnOnce(&mut UserSettingsStore, &mut SettingsOverrides) -> Result<()>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let (previous, current) = {
             let mut settings_guard = self.settings.lock().map_err(|e| {
                 anyhow::anyhow!("Failed to lock settings - mutex poisoned: {:?}", e)
             })?;
+            let mut overrides = self.overrides.lock().map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to lock settings overrides - mutex poisoned: {:?}",
+                    e
+                )
+            })?;
 
-            match *settings_guard {
-                Some(ref mut current_settings) => {
-                    current_settings.user = user_settings.clone();
-                }
-                None => {
-                    *settings_guard = Some(UserSettingsStore {
-                        version: self.version.clone(),
-                        user: user_settings.clone(),
-                    });
-                }
-            }
-        }
+            let previous = settings_guard
+                .as_ref()
+                .map(|stored| self.effective(stored, &overrides));
+            let mut current = settings_guard.clone().unwrap_or_else(|| UserSettingsStore {
+                version: self.version.clone(),
+                ..UserSettingsStore::default()
+            });
+            change(&mut current, &mut overrides)?;
+            current.enforce(&self.policy)?;
+            *settings_guard = Some(current.clone());
+            (previous, self.effective(&current, &overrides))
+        };
         self.save()?;
+        self.notify(previous.as_ref(), &current)?;
+        Ok(())
+    }
+
+    /// Applies command line overrides and the policy on top of the stored settings.
+    fn effective(
+        &self,
+        stored: &UserSettingsStore,
+        overrides: &SettingsOverrides,
+    ) -> UserSettingsStore {
+        let mut effective = overrides.apply(stored);
+        effective
+            .enforce(&self.policy)
+            .unwrap_or_else(|err| warn!("unable to enforce policy: {:?}", err));
+        effective
+    }
+
+    fn notify(
+        &self,
+        previous: Option<&UserSettingsStore>,
+        current: &UserSettingsStore,
+    ) -> Result<()> {
+        let mut changed_fields = changed_fields(previous.map(|p| &p.user), &current.user)?;
+        if previous.map(|p| &p.active_profile) != Some(&current.active_profile) {
+            changed_fields.push("active_profile".to_string());
+        }
+        if previous.map(|p| p.profile_names()) != Some(current.profile_names()) {
+            changed_fields.push("profiles".to_string());
+        }
+        if changed_fields.is_empty() {
+            return Ok(());
+        }
+
+        info!("settings changed: {:?}", changed_fields);
+        let event = SettingsChangedEvent {
+            changed_fields,
+            user: current.user.clone(),
+            active_profile: current.active_profile.clone(),
+            profile_names: current.profile_names(),
+        };
+        let listeners = self.listeners.lock().map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to lock settings listeners - mutex poisoned: {:?}",
+                e
+            )
+        })?;
+        for listener in listeners.iter() {
+            listener(&event);
+        }
+        Ok(())
+    }
+
+    /// True if the settings were written by a newer version and are never saved.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        if self.is_read_only() {
+            return Err(anyhow!(
+                "settings were saved by a newer version of Just Drink! and are read-only"
+            ));
+        }
         Ok(())
     }
 
-    // Save settings to the store
     pub fn save(&self) -> Result<()> {
         info!("Saving settings...");
+        self.ensure_writable()?;
 
         let settings = {
             let settings = self.settings.lock().map_err(|e| {
@@ -113,19 +428,48 @@ impl SettingsManager {
             settings.clone()
         };
 
-        if let Some(s) = settings {
-            // Serialize the settings
-            let json_data = serde_json::to_value(s)?;
+        if let Some(mut s) = settings {
+            // remember which app version wrote the settings last
+            s.version = self.version.clone();
 
-            // Update the store
-            self.store.set("data".to_string(), json_data);
-            self.store.save()?;
+            self.storage.write(&serde_json::to_value(s)?)?;
         }
 
         Ok(())
     }
 
+    /// Uses the default settings if none are stored, without saving them. The settings are
+    /// saved with the first change.
+    pub fn use_defaults(&self) -> Result<()> {
+        let mut settings = self
+            .settings
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock settings - mutex poisoned: {:?}", e))?;
+        if settings.is_none() {
+            *settings = Some(UserSettingsStore {
+                version: self.version.clone(),
+                ..UserSettingsStore::default()
+            });
+        }
+        Ok(())
+    }
+
+    pub fn policy(&self) -> &SettingsPolicy {
+        &self.policy
+    }
+
+    /// Returns the settings in effect, including command line overrides.
     pub fn get_settings(&self) -> Option<UserSettingsStore> {
+        let settings = self.get_stored_settings()?;
+        let overrides = self
+            .overrides
+            .lock()
+            .expect("get settings overrides - should not be poisoned");
+        Some(self.effective(&settings, &overrides))
+    }
+
+    /// Returns the settings as saved, without command line overrides.
+    pub fn get_stored_settings(&self) -> Option<UserSettingsStore> {
         let settings = self
             .settings
             .lock()
@@ -134,3 +478,127 @@ impl SettingsManager {
         settings.clone()
     }
 }
+
+fn changed_fields(
+    previous: Option<&SettingsUserDetails>,
+    current: &SettingsUserDetails,
+) -> Result<Vec<String>> {
+    let previous = previous.map(serde_json::to_value).transpose()?;
+    let current = serde_json::to_value(current)?;
+
+    let fields = current
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("settings are not a json object"))?
+        .iter()
+        .filter(|(field, value)| {
+            previous.as_ref().and_then(|p| p.get(field.as_str())) != Some(*value)
+        })
+        .map(|(field, _)| field.clone())
+        .collect();
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings_storage::{test_manager, MemoryStorage};
+
+    fn stored_user(storage: &MemoryStorage) -> SettingsUserDetails {
+        serde_json::from_value(storage.read().unwrap().unwrap()["user"].clone()).unwrap()
+    }
+
+    #[test]
+    fn test_update_is_saved_and_notified() {
+        let storage = Arc::new(MemoryStorage::default());
+        let sm = test_manager(&storage, SettingsOverrides::default());
+        let changed = Arc::new(Mutex::new(Vec::new()));
+        let listener_changed = changed.clone();
+        sm.subscribe(move |event| {
+            listener_changed
+                .lock()
+                .unwrap()
+                .extend(event.changed_fields.clone())
+        });
+
+        let mut user = UserSettingsStore::default().user;
+        sm.update_user(user.clone()).unwrap();
+        changed.lock().unwrap().clear();
+        user.drink_amount_ml = 2000;
+        sm.update_user(user).unwrap();
+
+        assert_eq!(*changed.lock().unwrap(), vec!["drink_amount_ml"]);
+        assert_eq!(stored_user(&storage).drink_amount_ml, 2000);
+        assert_eq!(storage.read().unwrap().unwrap()["version"], "1.1.0");
+    }
+
+    #[test]
+    fn test_overrides_are_not_saved() {
+        let storage = Arc::new(MemoryStorage::with_value(
+            serde_json::to_value(UserSettingsStore::default()).unwrap(),
+        ));
+        let overrides = SettingsOverrides {
+            next_break_duration_minutes: Some(5),
+            disable_idle_detection: true,
+            profile: None,
+        };
+        let sm = test_manager(&storage, overrides);
+
+        let mut user = sm.get_settings().unwrap().user;
+        assert_eq!(user.next_break_duration_minutes, 5);
+        assert!(!user.enable_idle_detection);
+        assert!(!user.pause_during_calls);
+
+        user.drink_amount_ml = 2000;
+        sm.update_user(user).unwrap();
+        let stored = stored_user(&storage);
+        assert_eq!(stored.next_break_duration_minutes, 15);
+        assert!(stored.enable_idle_detection);
+        assert!(stored.pause_during_calls);
+        assert_eq!(stored.drink_amount_ml, 2000);
+    }
+
+    #[test]
+    fn test_defaults_are_not_saved() {
+        let storage = Arc::new(MemoryStorage::default());
+        let sm = test_manager(&storage, SettingsOverrides::default());
+        assert!(sm.get_settings().is_none());
+
+        sm.use_defaults().unwrap();
+        assert_eq!(
+            sm.get_settings().unwrap().user.next_break_duration_minutes,
+            15
+        );
+        assert!(storage.read().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_newer_settings_are_not_saved() {
+        let mut data = serde_json::to_value(UserSettingsStore::default()).unwrap();
+        data["schema_version"] = (settings_migration::CURRENT_SCHEMA_VERSION + 1).into();
+        data["user"]["added_later"] = true.into();
+        let storage = Arc::new(MemoryStorage::with_value(data.clone()));
+        let sm = test_manager(&storage, SettingsOverrides::default());
+
+        assert!(sm.is_read_only());
+        assert_eq!(sm.get_settings().unwrap().user.drink_amount_ml, 3000);
+        let mut user = sm.get_settings().unwrap().user;
+        user.drink_amount_ml = 2000;
+        assert!(sm.update_user(user).is_err());
+        assert_eq!(sm.get_settings().unwrap().user.drink_amount_ml, 3000);
+        assert_eq!(storage.read().unwrap().unwrap(), data);
+    }
+
+    #[test]
+    fn test_old_settings_are_migrated_on_load() {
+        let storage = Arc::new(MemoryStorage::with_value(
+            serde_json::from_str(include_str!("../tests/fixtures/settings/v1.json")).unwrap(),
+        ));
+        let sm = test_manager(&storage, SettingsOverrides::default());
+
+        assert_eq!(sm.get_settings().unwrap().user.drink_amount_ml, 2500);
+        assert_eq!(
+            storage.read().unwrap().unwrap()["schema_version"],
+            settings_migration::CURRENT_SCHEMA_VERSION
+        );
+    }
+}
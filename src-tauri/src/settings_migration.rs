@@ -0,0 +1,344 @@
+use anyhow::anyhow;
+use log::{info, warn};
+use serde_json::{Map, Value};
+
+use crate::settings_manager::DEFAULT_PROFILE;
+
+/// Schema version of the user settings written by this build.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// Settings written before `schema_version` was introduced.
+const INITIAL_SCHEMA_VERSION: u32 = 1;
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), anyhow::Error>;
+
+/// Ordered migration steps, the step at index `n` migrates schema `n + 1` to `n + 2`.
+///
+/// A release bumps the schema at most once. New fields only need a serde default, a step
+/// is required when existing data changes its shape. Never change a released step.
+const MIGRATIONS: [Migration; (CURRENT_SCHEMA_VERSION - INITIAL_SCHEMA_VERSION) as usize] =
+    [v1_add_profiles];
+
+pub struct Migrated {
+    pub data: Value,
+    pub from_version: u32,
+}
+
+impl Migrated {
+    pub fn was_migrated(&self) -> bool {
+        self.from_version < CURRENT_SCHEMA_VERSION
+    }
+
+    /// Written by a newer version, saving them would drop the fields unknown to this one.
+    pub fn is_newer(&self) -> bool {
+        self.from_version > CURRENT_SCHEMA_VERSION
+    }
+}
+
+/// Migrates the stored user settings from any historical schema to the current one.
+pub fn migrate(mut data: Value) -> Result<Migrated, anyhow::Error> {
+    let store = data
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("settings are not a json object"))?;
+
+    let from_version = match store.get("schema_version") {
+        None => INITIAL_SCHEMA_VERSION,
+        Some(version) => version
+            .as_u64()
+            .map(|version| version as u32)
+            .ok_or_else(|| anyhow!("invalid settings schema version: {}", version))?,
+    };
+
+    if from_version < INITIAL_SCHEMA_VERSION {
+        return Err(anyhow!("unknown settings schema version: {}", from_version));
+    }
+
+    if from_version > CURRENT_SCHEMA_VERSION {
+        warn!(
+            "settings schema {} is newer than supported schema {}, they are used read-only",
+            from_version, CURRENT_SCHEMA_VERSION
+        );
+        return Ok(Migrated { data, from_version });
+    }
+
+    for (index, migration) in MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip((from_version - INITIAL_SCHEMA_VERSION) as usize)
+    {
+        let to_version = INITIAL_SCHEMA_VERSION + index as u32 + 1;
+        info!("migrate settings to schema version {}", to_version);
+        migration(store)
+            .map_err(|err| anyhow!("migration to schema {} failed: {}", to_version, err))?;
+        store.insert("schema_version".to_string(), Value::from(to_version));
+    }
+
+    Ok(Migrated { data, from_version })
+}
+
+fn user(store: &mut Map<String, Value>) -> Result<&mut Map<String, Value>, anyhow::Error> {
+    store
+        .get_mut("user")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| anyhow!("user settings are missing"))
+}
+
+/// Wraps the existing user settings into the active default profile.
+fn v1_add_profiles(store: &mut Map<String, Value>) -> Result<(), anyhow::Error> {
+    let user = user(store)?.clone();
+    store.insert("active_profile".to_string(), DEFAULT_PROFILE.into());
+    store.insert(
+        "profiles".to_string(),
+        serde_json::json!([{ "name": DEFAULT_PROFILE, "user": user, "schedule": null }]),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::session::{OverlayMonitor, OverlayPlacement, ReminderSound, ReminderStyle};
+    use crate::model::settings::{
+        default_escalation_minutes, default_http_api_port, default_overlay_layout,
+        default_snooze_minutes, default_sound_volume, default_typing_pause_seconds,
+    };
+    use crate::settings_manager::UserSettingsStore;
+
+    /// Settings as written by the 1.0.8 release and by every later change of the stored
+    /// fields, with the schema version they were written with. Fields added without a
+    /// schema bump rely on their serde default, each fixture proves the older file loads.
+    const FIXTURES: [(u32, &str, &str); 10] = [
+        (1, "v1", include_str!("../tests/fixtures/settings/v1.json")),
+        (
+            1,
+            "v1-typing-pause",
+            include_str!("../tests/fixtures/settings/v1-typing-pause.json"),
+        ),
+        (
+            2,
+            "v2-profiles",
+            include_str!("../tests/fixtures/settings/v2-profiles.json"),
+        ),
+        (
+            2,
+            "v2-http-api",
+            include_str!("../tests/fixtures/settings/v2-http-api.json"),
+        ),
+        (
+            2,
+            "v2-reminder-style",
+            include_str!("../tests/fixtures/settings/v2-reminder-style.json"),
+        ),
+        (
+            2,
+            "v2-escalation",
+            include_str!("../tests/fixtures/settings/v2-escalation.json"),
+        ),
+        (
+            2,
+            "v2-reminder-sound",
+            include_str!("../tests/fixtures/settings/v2-reminder-sound.json"),
+        ),
+        (
+            2,
+            "v2-overlay-monitor",
+            include_str!("../tests/fixtures/settings/v2-overlay-monitor.json"),
+        ),
+        (
+            2,
+            "v2-overlay-layout",
+            include_str!("../tests/fixtures/settings/v2-overlay-layout.json"),
+        ),
+        (
+            2,
+            "v2-snooze",
+            include_str!("../tests/fixtures/settings/v2-snooze.json"),
+        ),
+    ];
+
+    fn fixture(name: &str) -> &'static str {
+        FIXTURES
+            .iter()
+            .find(|(_, fixture_name, _)| *fixture_name == name)
+            .map(|(_, _, json)| *json)
+            .unwrap()
+    }
+
+    fn migrate_fixture(json: &str) -> (Migrated, UserSettingsStore) {
+        let migrated = migrate(serde_json::from_str(json).unwrap()).unwrap();
+        let store = serde_json::from_value(migrated.data.clone()).unwrap();
+        (migrated, store)
+    }
+
+    #[test]
+    fn test_every_fixture_migrates_to_current() {
+        for version in INITIAL_SCHEMA_VERSION..=CURRENT_SCHEMA_VERSION {
+            assert!(FIXTURES.iter().any(|(schema, _, _)| *schema == version));
+        }
+        for (version, _, json) in FIXTURES {
+            let (migrated, store) = migrate_fixture(json);
+            assert_eq!(migrated.from_version, version);
+            assert_eq!(store.schema_version, CURRENT_SCHEMA_VERSION);
+        }
+    }
+
+    #[test]
+    fn test_v1_becomes_default_profile() {
+        let (migrated, store) = migrate_fixture(fixture("v1"));
+        assert!(migrated.was_migrated());
+        assert_eq!(store.version, "1.0.8");
+        assert_eq!(store.user.next_break_duration_minutes, 30);
+        assert_eq!(store.user.drink_amount_ml, 2500);
+        assert!(!store.user.enable_idle_detection);
+        assert_eq!(store.active_profile, DEFAULT_PROFILE);
+        assert_eq!(store.profile_names(), vec![DEFAULT_PROFILE]);
+        assert_eq!(store.profiles[0].user.drink_amount_ml, 2500);
+    }
+
+    #[test]
+    fn test_missing_fields_use_defaults() {
+        let (_, store) = migrate_fixture(fixture("v1"));
+        assert!(store.user.pause_during_calls);
+        assert!(!store.user.wait_for_typing_pause);
+        assert_eq!(
+            store.user.typing_pause_seconds,
+            default_typing_pause_seconds()
+        );
+        assert!(store.user.reduce_work_on_low_battery);
+        for profile in &store.profiles {
+            assert_eq!(profile.user.http_api_port, default_http_api_port());
+            assert_eq!(profile.user.reminder_style, ReminderStyle::Overlay);
+            assert_eq!(profile.user.reminder_sound, ReminderSound::Character);
+            assert_eq!(profile.user.overlay_monitor, OverlayMonitor::Primary);
+            assert_eq!(profile.user.overlay_layout, default_overlay_layout());
+        }
+    }
+
+    #[test]
+    fn test_typing_pause_moves_into_default_profile() {
+        let (migrated, store) = migrate_fixture(fixture("v1-typing-pause"));
+        assert!(migrated.was_migrated());
+        assert!(store.user.wait_for_typing_pause);
+        assert_eq!(store.active_profile, DEFAULT_PROFILE);
+        assert_eq!(store.profile_names(), vec![DEFAULT_PROFILE]);
+        assert_eq!(store.profiles[0].user.typing_pause_seconds, 5);
+    }
+
+    #[test]
+    fn test_profiles_get_default_http_api() {
+        let (migrated, store) = migrate_fixture(fixture("v2-profiles"));
+        assert!(!migrated.was_migrated());
+        assert!(!store.user.http_api_enabled);
+        for profile in &store.profiles {
+            assert_eq!(profile.user.http_api_port, default_http_api_port());
+        }
+    }
+
+    #[test]
+    fn test_http_api_gets_overlay_reminder_style() {
+        let (_, store) = migrate_fixture(fixture("v2-http-api"));
+        assert_eq!(store.user.reminder_style, ReminderStyle::Overlay);
+        for profile in &store.profiles {
+            assert_eq!(profile.user.reminder_style, ReminderStyle::Overlay);
+        }
+    }
+
+    #[test]
+    fn test_reminder_style_gets_disabled_escalation() {
+        let (_, store) = migrate_fixture(fixture("v2-reminder-style"));
+        assert_eq!(store.user.reminder_style, ReminderStyle::Notification);
+        for profile in &store.profiles {
+            assert!(!profile.user.escalate_reminders);
+            assert_eq!(
+                profile.user.escalation_minutes,
+                default_escalation_minutes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_escalation_gets_default_reminder_sound() {
+        let (_, store) = migrate_fixture(fixture("v2-escalation"));
+        assert!(store.user.escalate_reminders);
+        for profile in &store.profiles {
+            assert_eq!(profile.user.reminder_sound, ReminderSound::Character);
+            assert_eq!(profile.user.sound_volume, default_sound_volume());
+            assert!(!profile.user.mute_during_quiet_hours);
+        }
+    }
+
+    #[test]
+    fn test_reminder_sound_gets_primary_overlay_monitor() {
+        let (_, store) = migrate_fixture(fixture("v2-reminder-sound"));
+        assert_eq!(store.user.reminder_sound, ReminderSound::Sparkling);
+        assert_eq!(store.user.overlay_monitor, OverlayMonitor::Primary);
+        for profile in &store.profiles {
+            assert_eq!(profile.user.overlay_monitor, OverlayMonitor::Primary);
+        }
+    }
+
+    #[test]
+    fn test_overlay_monitor_gets_full_screen_overlay_layout() {
+        let (_, store) = migrate_fixture(fixture("v2-overlay-monitor"));
+        assert_eq!(store.user.overlay_monitor, OverlayMonitor::Cursor);
+        for profile in &store.profiles {
+            assert_eq!(profile.user.overlay_layout, default_overlay_layout());
+        }
+    }
+
+    #[test]
+    fn test_overlay_layout_gets_default_snooze() {
+        let (_, store) = migrate_fixture(fixture("v2-overlay-layout"));
+        assert_eq!(
+            store.user.overlay_layout.placement,
+            OverlayPlacement::FullScreen
+        );
+        for profile in &store.profiles {
+            assert_eq!(profile.user.snooze_minutes, default_snooze_minutes());
+        }
+    }
+
+    #[test]
+    fn test_current_is_not_migrated() {
+        let (migrated, store) = migrate_fixture(fixture("v2-snooze"));
+        assert!(!migrated.was_migrated());
+        assert!(!migrated.is_newer());
+        assert_eq!(store.active_profile, "Focus");
+        assert!(store.profiles[1].schedule.is_some());
+        assert!(store.profiles[1].user.http_api_enabled);
+        assert_eq!(
+            store.profiles[1].user.reminder_style,
+            ReminderStyle::Notification
+        );
+        assert!(store.profiles[1].user.escalate_reminders);
+        assert_eq!(
+            store.profiles[1].user.reminder_sound,
+            ReminderSound::Sparkling
+        );
+        assert!(store.profiles[1].user.is_quiet(23 * 60));
+        assert!(!store.profiles[1].user.is_quiet(12 * 60));
+        assert_eq!(store.profiles[1].user.overlay_monitor, OverlayMonitor::All);
+        assert_eq!(
+            store.profiles[1].user.overlay_layout.placement,
+            OverlayPlacement::Corner
+        );
+        assert!(store.profiles[1].user.overlay_layout.interactive);
+        assert_eq!(store.profiles[1].user.snooze_minutes, 10);
+    }
+
+    #[test]
+    fn test_newer_schema_is_loaded_as_is() {
+        let mut data: Value = serde_json::from_str(fixture("v2-snooze")).unwrap();
+        data["schema_version"] = Value::from(CURRENT_SCHEMA_VERSION + 1);
+        let migrated = migrate(data.clone()).unwrap();
+        assert!(migrated.is_newer());
+        assert!(!migrated.was_migrated());
+        assert_eq!(migrated.data, data);
+    }
+
+    #[test]
+    fn test_missing_user_fails() {
+        let data = serde_json::json!({ "version": "1.0.8" });
+        assert!(migrate(data).is_err());
+    }
+}
@@ -0,0 +1,54 @@
+use crate::model::settings::{SettingsProfile, SettingsUserDetails};
+use crate::settings_manager::UserSettingsStore;
+
+/// Settings given on the command line, they apply to the running session and are never saved.
+#[derive(Debug, Clone, Default)]
+pub struct SettingsOverrides {
+    pub next_break_duration_minutes: Option<u32>,
+    pub disable_idle_detection: bool,
+    pub profile: Option<String>,
+}
+
+impl SettingsOverrides {
+    /// Returns the settings as seen by the running app.
+    pub fn apply(&self, store: &UserSettingsStore) -> UserSettingsStore {
+        let mut effective = store.clone();
+        if let Some(profile) = self.session_profile(store) {
+            effective.user = profile.user.clone();
+            effective.active_profile = profile.name.clone();
+        }
+        if let Some(minutes) = self.next_break_duration_minutes {
+            effective.user.next_break_duration_minutes = minutes;
+        }
+        if self.disable_idle_detection {
+            effective.user.enable_idle_detection = false;
+            effective.user.pause_during_calls = false;
+        }
+        effective
+    }
+
+    /// Name of the profile changed by the user, the session profile if it exists.
+    pub fn target_profile(&self, store: &UserSettingsStore) -> String {
+        self.session_profile(store)
+            .map(|profile| profile.name.clone())
+            .unwrap_or_else(|| store.active_profile.clone())
+    }
+
+    /// Keeps the stored values of overridden fields, unless the user changed them.
+    pub fn restore(&self, stored: &SettingsUserDetails, user: &mut SettingsUserDetails) {
+        if self.next_break_duration_minutes == Some(user.next_break_duration_minutes) {
+            user.next_break_duration_minutes = stored.next_break_duration_minutes;
+        }
+        if self.disable_idle_detection && !user.enable_idle_detection {
+            user.enable_idle_detection = stored.enable_idle_detection;
+        }
+        if self.disable_idle_detection && !user.pause_during_calls {
+            user.pause_during_calls = stored.pause_during_calls;
+        }
+    }
+
+    fn session_profile<'a>(&self, store: &'a UserSettingsStore) -> Option<&'a SettingsProfile> {
+        let name = self.profile.as_ref()?;
+        store.profiles.iter().find(|profile| &profile.name == name)
+    }
+}
@@ -0,0 +1,186 @@
+//! System wide policy, managed by administrators, which overrides user settings.
+//!
+//! ```json
+//! {
+//!   "locked": { "allow_tracking": false, "beta_version": false },
+//!   "min_next_break_duration_minutes": 30
+//! }
+//! ```
+use crate::model::settings::SettingsUserDetails;
+use crate::settings_manager::UserSettingsStore;
+use crate::settings_validation::{InvalidField, SettingsValidationError, BREAK_DURATION_MINUTES};
+use anyhow::anyhow;
+use log::info;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+#[cfg(target_os = "linux")]
+const POLICY_PATH: &str = "/etc/justdrink/policy.json";
+#[cfg(target_os = "macos")]
+const POLICY_PATH: &str = "/Library/Application Support/JustDrink/policy.json";
+#[cfg(target_os = "windows")]
+const POLICY_PATH: &str = "JustDrink\\policy.json";
+
+const LOCKED_MESSAGE: &str = "is managed by your administrator";
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SettingsPolicy {
+    /// Enforced values of user settings, keyed by field name.
+    locked: Map<String, Value>,
+    min_next_break_duration_minutes: Option<u32>,
+}
+
+impl SettingsPolicy {
+    /// Reads the policy file, a missing file means no policy.
+    pub fn load() -> Result<Self, anyhow::Error> {
+        let path = policy_path();
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(anyhow!("unable to read {:?}: {}", path, err)),
+        };
+
+        let policy: SettingsPolicy = serde_json::from_str(&json)
+            .map_err(|err| anyhow!("invalid policy {:?}: {}", path, err))?;
+        // fail early on unknown fields or wrong types instead of on the first update
+        let mut user = serde_json::to_value(UserSettingsStore::default().user)?;
+        for field in policy.locked.keys() {
+            if user.get(field).is_none() {
+                return Err(anyhow!(
+                    "invalid policy {:?}: unknown field '{}'",
+                    path,
+                    field
+                ));
+            }
+        }
+        policy.lock_fields(&mut user);
+        serde_json::from_value::<SettingsUserDetails>(user)
+            .map_err(|err| anyhow!("invalid policy {:?}: {}", path, err))?;
+
+        info!(
+            "settings policy loaded from {:?}, locked fields: {:?}",
+            path,
+            policy.locked_fields()
+        );
+        Ok(policy)
+    }
+
+    pub fn locked_fields(&self) -> Vec<String> {
+        self.locked.keys().cloned().collect()
+    }
+
+    pub fn min_break_duration_minutes(&self) -> u32 {
+        self.min_next_break_duration_minutes
+            .unwrap_or(*BREAK_DURATION_MINUTES.start())
+            .max(*BREAK_DURATION_MINUTES.start())
+    }
+
+    /// Returns the user settings with all policy values enforced.
+    pub fn apply(&self, user: &SettingsUserDetails) -> Result<SettingsUserDetails, anyhow::Error> {
+        let mut value = serde_json::to_value(user)?;
+        self.lock_fields(&mut value);
+        let mut user: SettingsUserDetails = serde_json::from_value(value)?;
+        user.next_break_duration_minutes = user
+            .next_break_duration_minutes
+            .max(self.min_break_duration_minutes());
+        Ok(user)
+    }
+
+    /// Rejects user changes which violate the policy.
+    pub fn check(&self, user: &SettingsUserDetails) -> Result<(), SettingsValidationError> {
+        let value = serde_json::to_value(user).unwrap_or_default();
+        let mut invalid_fields: Vec<InvalidField> = self
+            .locked
+            .iter()
+            .filter(|(field, locked)| value.get(field.as_str()) != Some(*locked))
+            .map(|(field, _)| InvalidField {
+                field: field.clone(),
+                message: LOCKED_MESSAGE.to_string(),
+            })
+            .collect();
+
+        if user.next_break_duration_minutes < self.min_break_duration_minutes() {
+            invalid_fields.push(InvalidField {
+                field: "next_break_duration_minutes".to_string(),
+                message: format!(
+                    "must be at least {} minutes",
+                    self.min_break_duration_minutes()
+                ),
+            });
+        }
+
+        if invalid_fields.is_empty() {
+            Ok(())
+        } else {
+            Err(SettingsValidationError { invalid_fields })
+        }
+    }
+
+    fn lock_fields(&self, user: &mut Value) {
+        if let Some(user) = user.as_object_mut() {
+            for (field, locked) in &self.locked {
+                user.insert(field.clone(), locked.clone());
+            }
+        }
+    }
+}
+
+fn policy_path() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let program_data =
+            std::env::var("ProgramData").unwrap_or_else(|_| "C:\\ProgramData".to_string());
+        PathBuf::from(program_data).join(POLICY_PATH)
+    }
+    #[cfg(not(target_os = "windows"))]
+    PathBuf::from(POLICY_PATH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> SettingsPolicy {
+        serde_json::from_value(serde_json::json!({
+            "locked": { "allow_tracking": false, "beta_version": false },
+            "min_next_break_duration_minutes": 30
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_apply_enforces_locked_fields_and_minimum() {
+        let mut user = UserSettingsStore::default().user;
+        user.allow_tracking = true;
+        user.next_break_duration_minutes = 15;
+
+        let user = policy().apply(&user).unwrap();
+        assert!(!user.allow_tracking);
+        assert!(!user.beta_version);
+        assert_eq!(user.next_break_duration_minutes, 30);
+        assert_eq!(policy().check(&user), Ok(()));
+    }
+
+    #[test]
+    fn test_check_rejects_changes_to_locked_fields() {
+        let mut user = policy().apply(&UserSettingsStore::default().user).unwrap();
+        user.allow_tracking = true;
+        user.next_break_duration_minutes = 20;
+
+        let fields: Vec<String> = policy()
+            .check(&user)
+            .unwrap_err()
+            .invalid_fields
+            .into_iter()
+            .map(|invalid| invalid.field)
+            .collect();
+        assert_eq!(
+            fields,
+            vec!["allow_tracking", "next_break_duration_minutes"]
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use crate::model::settings::{ProfileSchedule, SettingsProfiles};
+use crate::settings_validation::Validate;
+use crate::worker::Worker;
+use crate::SettingsManagerState;
+use chrono::{Datelike, Local, Timelike};
+use log::{info, warn};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State, Wry};
+
+const MAX_PROFILE_NAME_LENGTH: usize = 32;
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[specta::specta]
+#[tauri::command]
+pub fn load_profiles(
+    settings_manager: State<'_, SettingsManagerState>,
+) -> Result<SettingsProfiles, String> {
+    let settings = settings_manager
+        .get_settings()
+        .ok_or_else(|| "Just Drink! is not configured yet".to_string())?;
+    Ok(SettingsProfiles {
+        active_profile: settings.active_profile,
+        profiles: settings.profiles,
+    })
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn switch_profile(
+    name: String,
+    settings_manager: State<'_, SettingsManagerState>,
+) -> Result<(), String> {
+    settings_manager
+        .switch_profile(&name)
+        .map_err(|err| format!("unable to switch profile: {}", err))
+}
+
+/// Saves the active settings as new profile, or updates the schedule of an existing one.
+#[specta::specta]
+#[tauri::command]
+pub fn save_profile(
+    name: String,
+    schedule: Option<ProfileSchedule>,
+    settings_manager: State<'_, SettingsManagerState>,
+) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_PROFILE_NAME_LENGTH {
+        return Err(format!(
+            "profile name must have 1 to {} characters",
+            MAX_PROFILE_NAME_LENGTH
+        ));
+    }
+    if let Some(schedule) = &schedule {
+        schedule.validate().map_err(|err| err.to_string())?;
+    }
+    settings_manager
+        .save_profile(name, schedule)
+        .map_err(|err| format!("unable to save profile: {}", err))
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn delete_profile(
+    name: String,
+    settings_manager: State<'_, SettingsManagerState>,
+) -> Result<(), String> {
+    settings_manager
+        .delete_profile(&name)
+        .map_err(|err| format!("unable to delete profile: {}", err))
+}
+
+/// Activates scheduled profiles when their time window starts.
+///
+/// Only the transition into a window switches the profile, so a profile selected by hand
+/// stays active until the next scheduled window begins.
+pub struct ProfileScheduler {
+    worker: Worker,
+}
+
+impl ProfileScheduler {
+    pub fn start(app: &AppHandle<Wry>) -> Result<Self, anyhow::Error> {
+        let app_handle = app.app_handle().clone();
+        let mut scheduled: Option<String> = None;
+        let worker = Worker::start("profile-schedule", SCHEDULE_CHECK_INTERVAL, move || {
+            let current = scheduled_profile(&app_handle);
+            if current != scheduled {
+                if let Some(name) = &current {
+                    info!("scheduled profile {name} starts");
+                    app_handle
+                        .state::<SettingsManagerState>()
+                        .switch_profile(name)
+                        .unwrap_or_else(|err| warn!("unable to switch profile: {err}"));
+                }
+                scheduled = current;
+            }
+        })?;
+        Ok(ProfileScheduler { worker })
+    }
+
+    /// Stops the schedule, waits for the background thread to finish.
+    pub fn stop(&self) {
+        self.worker.stop();
+    }
+}
+
+fn scheduled_profile(app: &AppHandle<Wry>) -> Option<String> {
+    let settings = app.state::<SettingsManagerState>().get_settings()?;
+    let now = Local::now();
+    let day = now.weekday().number_from_monday();
+    let minute = now.hour() * 60 + now.minute();
+
+    settings
+        .profiles
+        .into_iter()
+        .find(|profile| {
+            profile
+                .schedule
+                .as_ref()
+                .is_some_and(|schedule| schedule.matches(day, minute))
+        })
+        .map(|profile| profile.name)
+}
@@ -0,0 +1,279 @@
+use anyhow::anyhow;
+use chrono::Utc;
+use log::{debug, info, warn};
+use serde_json::{Map, Value};
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Persistence of a single settings document.
+///
+/// Implementations must replace the document atomically and keep the previous document
+/// as last known good backup.
+pub trait SettingsStorage: Send + Sync {
+    /// Returns `None` if nothing was stored yet.
+    fn read(&self) -> Result<Option<Value>, anyhow::Error>;
+    fn read_backup(&self) -> Result<Option<Value>, anyhow::Error>;
+    fn write(&self, value: &Value) -> Result<(), anyhow::Error>;
+    /// Moves the damaged document out of the way, returns where it was kept.
+    fn set_aside(&self) -> Result<Option<PathBuf>, anyhow::Error>;
+    fn describe(&self) -> String;
+}
+
+pub struct Loaded<T> {
+    pub value: T,
+    pub recovery: Option<Recovery>,
+}
+
+/// The settings were damaged and have been restored from the backup.
+pub struct Recovery {
+    pub error: anyhow::Error,
+    pub damaged_copy: Option<PathBuf>,
+}
+
+/// Reads the settings, `parse` decides whether the content is usable.
+/// Damaged settings are set aside and replaced by the backup. Returns `None` if there are
+/// no settings yet.
+pub fn load<T>(
+    storage: &dyn SettingsStorage,
+    parse: impl Fn(Value) -> Result<T, anyhow::Error>,
+) -> Result<Option<Loaded<T>>, anyhow::Error> {
+    let error = match storage
+        .read()
+        .and_then(|value| value.map(&parse).transpose())
+    {
+        Ok(None) => return Ok(None),
+        Ok(Some(value)) => {
+            return Ok(Some(Loaded {
+                value,
+                recovery: None,
+            }))
+        }
+        Err(error) => error,
+    };
+
+    warn!(
+        "settings in {} are damaged: {:?}",
+        storage.describe(),
+        error
+    );
+    let damaged_copy = storage.set_aside()?;
+
+    let value = storage
+        .read_backup()
+        .and_then(|value| value.ok_or_else(|| anyhow!("no backup available")))
+        .map_err(|err| anyhow!("{} and the backup is unusable: {}", error, err))?;
+    let parsed = parse(value.clone())
+        .map_err(|err| anyhow!("{} and the backup is unusable: {}", error, err))?;
+
+    storage.write(&value)?;
+    info!("settings in {} restored from backup", storage.describe());
+    Ok(Some(Loaded {
+        value: parsed,
+        recovery: Some(Recovery {
+            error,
+            damaged_copy,
+        }),
+    }))
+}
+
+/// JSON settings file, compatible with the files written by `tauri_plugin_store`.
+///
+/// Writes go to a temporary file which is renamed over the old one, so a crash never leaves
+/// a partially written file behind.
+pub struct FileStorage {
+    path: PathBuf,
+    key: String,
+}
+
+impl FileStorage {
+    pub fn new(path: PathBuf, key: &str) -> Self {
+        FileStorage {
+            path,
+            key: key.to_string(),
+        }
+    }
+
+    fn backup_path(&self) -> PathBuf {
+        self.path.with_extension("json.bak")
+    }
+
+    fn read_file(&self, path: &Path) -> Result<Option<Value>, anyhow::Error> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let mut root: Map<String, Value> = serde_json::from_str(&json)?;
+        root.remove(&self.key)
+            .map(Some)
+            .ok_or_else(|| anyhow!("'{}' is missing", self.key))
+    }
+
+    fn write_file(&self, path: &Path, value: &Value) -> Result<(), anyhow::Error> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut root = Map::new();
+        root.insert(self.key.clone(), value.clone());
+
+        let temp_path = path.with_extension("tmp");
+        let mut file = File::create(&temp_path)?;
+        file.write_all(&serde_json::to_vec_pretty(&root)?)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+}
+
+impl SettingsStorage for FileStorage {
+    fn read(&self) -> Result<Option<Value>, anyhow::Error> {
+        self.read_file(&self.path)
+    }
+
+    fn read_backup(&self) -> Result<Option<Value>, anyhow::Error> {
+        self.read_file(&self.backup_path())
+    }
+
+    fn write(&self, value: &Value) -> Result<(), anyhow::Error> {
+        // only a readable file is worth to be kept as last known good version
+        if let Ok(Some(previous)) = self.read() {
+            self.write_file(&self.backup_path(), &previous)?;
+        }
+        self.write_file(&self.path, value)?;
+        debug!("settings written to {:?}", self.path);
+        Ok(())
+    }
+
+    fn set_aside(&self) -> Result<Option<PathBuf>, anyhow::Error> {
+        let damaged_copy = self.path.with_extension(format!(
+            "damaged-{}.json",
+            Utc::now().format("%Y%m%d-%H%M%S")
+        ));
+        fs::rename(&self.path, &damaged_copy)?;
+        Ok(Some(damaged_copy))
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self.path)
+    }
+}
+
+/// Keeps the settings in memory only, for tests and sessions which must not touch the disk.
+#[derive(Default)]
+pub struct MemoryStorage {
+    value: Mutex<Option<Value>>,
+    backup: Mutex<Option<Value>>,
+}
+
+impl MemoryStorage {
+    pub fn with_value(value: Value) -> Self {
+        MemoryStorage {
+            value: Mutex::new(Some(value)),
+            backup: Mutex::new(None),
+        }
+    }
+}
+
+impl SettingsStorage for MemoryStorage {
+    fn read(&self) -> Result<Option<Value>, anyhow::Error> {
+        Ok(self
+            .value
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .clone())
+    }
+
+    fn read_backup(&self) -> Result<Option<Value>, anyhow::Error> {
+        Ok(self
+            .backup
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .clone())
+    }
+
+    fn write(&self, value: &Value) -> Result<(), anyhow::Error> {
+        let previous = self
+            .value
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .replace(value.clone());
+        if previous.is_some() {
+            *self.backup.lock().map_err(|e| anyhow!(e.to_string()))? = previous;
+        }
+        Ok(())
+    }
+
+    fn set_aside(&self) -> Result<Option<PathBuf>, anyhow::Error> {
+        self.value
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .take();
+        Ok(None)
+    }
+
+    fn describe(&self) -> String {
+        "memory".to_string()
+    }
+}
+
+/// Settings manager on top of the given memory storage, loaded and ready for tests.
+#[cfg(test)]
+pub(crate) fn test_manager(
+    storage: &std::sync::Arc<MemoryStorage>,
+    overrides: crate::settings_overrides::SettingsOverrides,
+) -> crate::settings_manager::SettingsManager {
+    let sm = crate::settings_manager::SettingsManager::with_storage(
+        storage.clone(),
+        "1.1.0".to_string(),
+        crate::settings_policy::SettingsPolicy::default(),
+        overrides,
+    );
+    sm.load().unwrap();
+    sm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn file_storage(name: &str) -> FileStorage {
+        let dir = std::env::temp_dir().join(format!("justdrink-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        FileStorage::new(dir.join("settings.json"), "data")
+    }
+
+    fn parse(value: Value) -> Result<u64, anyhow::Error> {
+        value["count"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("count is missing"))
+    }
+
+    #[test]
+    fn test_missing_file_has_no_settings() {
+        assert!(load(&file_storage("missing"), parse).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_damaged_file_is_restored_from_backup() {
+        let storage = file_storage("damaged");
+        storage.write(&json!({ "count": 1 })).unwrap();
+        storage.write(&json!({ "count": 2 })).unwrap();
+        fs::write(&storage.path, "{ \"data\": { \"cou").unwrap();
+
+        let loaded = load(&storage, parse).unwrap().unwrap();
+        assert_eq!(loaded.value, 1);
+        assert!(loaded.recovery.unwrap().damaged_copy.unwrap().exists());
+        assert_eq!(load(&storage, parse).unwrap().unwrap().value, 1);
+    }
+
+    #[test]
+    fn test_unusable_backup_fails() {
+        let storage = MemoryStorage::with_value(json!({ "count": 1 }));
+        storage.write(&json!({ "unknown": 2 })).unwrap();
+        storage.write(&json!({ "unknown": 3 })).unwrap();
+        assert!(load(&storage, parse).is_err());
+    }
+}
@@ -1,10 +1,13 @@
+use crate::cli;
 use crate::model::settings::SettingsSystemDetails;
+use crate::settings_storage::{self, FileStorage, MemoryStorage, SettingsStorage};
 use anyhow::Error;
-use chrono::{Duration, Utc};
+use chrono::{Duration, Local, Utc};
 use log::{debug, error, warn};
+use rand::Rng;
 use std::string::ToString;
-use tauri::{AppHandle, Manager, Runtime};
-use tauri_plugin_store::StoreBuilder;
+use std::sync::Arc;
+use tauri::AppHandle;
 
 const STORE_NAME: &str = "mm-system-config.json";
 const ROOT_PATH: &str = "system";
@@ -12,82 +15,146 @@ const ROOT_PATH: &str = "system";
 #[derive(Clone)]
 pub(crate) struct SettingsSystem {
     settings: SettingsSystemDetails,
+    storage: Arc<dyn SettingsStorage>,
 }
 
 impl SettingsSystem {
     pub fn load(app: &AppHandle) -> SettingsSystem {
-        let settings = Self::load_settings_store(app).unwrap_or_else(|err| {
+        let storage: Arc<dyn SettingsStorage> = match cli::config_path(app, STORE_NAME) {
+            Ok(path) => Arc::new(FileStorage::new(path, ROOT_PATH)),
+            Err(err) => {
+                error!(
+                    "no location for system settings, keep them in memory: {:?}",
+                    err
+                );
+                Arc::new(MemoryStorage::default())
+            }
+        };
+        Self::with_storage(storage)
+    }
+
+    pub fn with_storage(storage: Arc<dyn SettingsStorage>) -> SettingsSystem {
+        let settings = Self::load_settings_store(storage.as_ref()).unwrap_or_else(|err| {
             warn!("system store settings not found: {:?}", err);
             SettingsSystemDetails {
                 session_count: 0,
                 feedback_provided: false,
                 last_update_check_date: Utc::now(),
+                intake_date: None,
+                intake_ml: 0,
+                http_api_token: None,
             }
         });
-        SettingsSystem { settings }
+        SettingsSystem { settings, storage }
     }
 
     pub fn settings(&self) -> SettingsSystemDetails {
         self.settings.clone()
     }
 
-    pub fn feedback_given<R>(&mut self, app: &AppHandle<R>) -> ()
-    where
-        R: Runtime,
-    {
+    pub fn feedback_given(&mut self) -> () {
         self.settings.feedback_provided = true;
-        self.write_settings(app)
+        self.write_settings()
             .unwrap_or_else(|err| error!("unable to write system settings {}", err))
     }
 
-    pub fn increase_session_count<R>(&mut self, app: &AppHandle<R>) -> ()
-    where
-        R: Runtime,
-    {
+    pub fn increase_session_count(&mut self) -> () {
         self.settings.session_count += 1;
-        self.write_settings(app)
+        self.write_settings()
             .unwrap_or_else(|err| error!("unable to write system settings {}", err))
     }
 
-    pub fn set_last_check_date<R>(&mut self, app: &AppHandle<R>) -> Result<(), Error>
-    where
-        R: Runtime,
-    {
+    pub fn set_last_check_date(&mut self) -> Result<(), Error> {
         self.settings.last_update_check_date = Utc::now();
-        self.write_settings(app)?;
+        self.write_settings()?;
         Ok(())
     }
 
-    pub fn updater_check_needed(&self) -> bool {
-        (self.settings.last_update_check_date + Duration::days(2)) < Utc::now()
+    /// Adds a drink to the intake of today, returns the total of today.
+    pub fn log_intake(&mut self, ml: u32) -> Result<u32, Error> {
+        let today = Local::now().date_naive();
+        if self.settings.intake_date != Some(today) {
+            self.settings.intake_date = Some(today);
+            self.settings.intake_ml = 0;
+        }
+        self.settings.intake_ml += ml;
+        self.write_settings()?;
+        Ok(self.settings.intake_ml)
     }
 
-    fn write_settings<R>(&self, app: &AppHandle<R>) -> Result<(), anyhow::Error>
-    where
-        R: Runtime,
-    {
-        debug!("save system settings");
-        let store = StoreBuilder::new(app.app_handle(), STORE_NAME).build()?;
+    pub fn intake_today(&self) -> u32 {
+        if self.settings.intake_date == Some(Local::now().date_naive()) {
+            self.settings.intake_ml
+        } else {
+            0
+        }
+    }
 
-        let json_data = serde_json::to_value(self.settings.clone())
-            .map_err(|e| tauri_plugin_store::Error::Serialize(Box::new(e)))?;
+    /// Token of the HTTP interface, `None` until the interface was enabled once.
+    pub fn http_api_token(&self) -> Option<String> {
+        self.settings.http_api_token.clone()
+    }
 
-        store.set(ROOT_PATH.to_string(), json_data);
-        store.save()?;
+    /// Token of the HTTP interface, generated on first use.
+    pub fn ensure_http_api_token(&mut self) -> Result<String, Error> {
+        match &self.settings.http_api_token {
+            Some(token) => Ok(token.clone()),
+            None => self.regenerate_http_api_token(),
+        }
+    }
 
-        Ok(())
+    pub fn regenerate_http_api_token(&mut self) -> Result<String, Error> {
+        let bytes: [u8; 32] = rand::rng().random();
+        let token: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.settings.http_api_token = Some(token.clone());
+        self.write_settings()?;
+        Ok(token)
     }
 
-    fn load_settings_store(app: &AppHandle) -> Result<SettingsSystemDetails, anyhow::Error> {
-        let store = StoreBuilder::new(app.app_handle(), STORE_NAME).build()?;
+    pub fn updater_check_needed(&self) -> bool {
+        (self.settings.last_update_check_date + Duration::days(2)) < Utc::now()
+    }
 
-        let data_json = store
-            .get(ROOT_PATH.to_string())
-            .ok_or_else(|| anyhow::anyhow!("can't find settings in {}", ROOT_PATH))?;
+    fn write_settings(&self) -> Result<(), anyhow::Error> {
+        debug!("save system settings");
+        self.storage
+            .write(&serde_json::to_value(self.settings.clone())?)?;
 
-        let settings: SettingsSystemDetails = serde_json::from_value(data_json.clone())
-            .map_err(|e| tauri_plugin_store::Error::Deserialize(Box::new(e)))?;
+        Ok(())
+    }
+
+    fn load_settings_store(
+        storage: &dyn SettingsStorage,
+    ) -> Result<SettingsSystemDetails, anyhow::Error> {
+        let loaded = settings_storage::load(storage, |data| {
+            Ok(serde_json::from_value::<SettingsSystemDetails>(data)?)
+        })?
+        .ok_or_else(|| anyhow::anyhow!("can't find settings in {}", storage.describe()))?;
+
+        if let Some(recovery) = loaded.recovery {
+            warn!(
+                "system settings restored from backup, damaged file kept at {:?}: {:?}",
+                recovery.damaged_copy, recovery.error
+            );
+        }
+        Ok(loaded.value)
+    }
+}
 
-        Ok(settings)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reading_the_token_writes_nothing() {
+        let storage = Arc::new(MemoryStorage::default());
+        let mut system = SettingsSystem::with_storage(storage.clone());
+        assert_eq!(system.http_api_token(), None);
+        assert!(storage.read().unwrap().is_none());
+
+        let token = system.ensure_http_api_token().unwrap();
+        assert_eq!(system.http_api_token(), Some(token.clone()));
+        assert_eq!(system.ensure_http_api_token().unwrap(), token);
+        assert!(storage.read().unwrap().is_some());
     }
 }
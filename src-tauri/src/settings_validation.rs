@@ -0,0 +1,315 @@
+use crate::model::session::ReminderSound;
+use crate::model::settings::{ProfileSchedule, SettingsUserDetails};
+use crate::model::welcome::WelcomeUserSettings;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::Read;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+pub const BREAK_DURATION_MINUTES: RangeInclusive<u32> = 1..=720;
+pub const DAILY_GOAL_ML: RangeInclusive<u32> = 250..=10_000;
+pub const TYPING_PAUSE_SECONDS: RangeInclusive<u32> = 1..=60;
+pub const TYPING_PAUSE_MAX_WAIT_SECONDS: RangeInclusive<u32> = 1..=600;
+pub const SCHEDULE_DAYS: RangeInclusive<u32> = 1..=7;
+pub const SCHEDULE_MINUTES: RangeInclusive<u32> = 0..=1439;
+pub const INTAKE_ML: RangeInclusive<u32> = 1..=5000;
+pub const HTTP_API_PORT: RangeInclusive<u32> = 1024..=65_535;
+pub const ESCALATION_MINUTES: RangeInclusive<u32> = 1..=60;
+pub const SNOOZE_MINUTES: RangeInclusive<u32> = 1..=60;
+pub const SOUND_VOLUME: RangeInclusive<u32> = 0..=100;
+pub const OVERLAY_SIZE_PERCENT: RangeInclusive<u32> = 10..=50;
+pub const OVERLAY_OPACITY: RangeInclusive<u32> = 20..=100;
+/// Extensions of the audio files the overlay is able to play.
+pub const SOUND_FILE_EXTENSIONS: [&str; 3] = ["mp3", "ogg", "wav"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Type, PartialEq)]
+pub struct InvalidField {
+    pub(crate) field: String,
+    pub(crate) message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Type, PartialEq)]
+pub struct SettingsValidationError {
+    pub(crate) invalid_fields: Vec<InvalidField>,
+}
+
+impl Display for SettingsValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<String> = self
+            .invalid_fields
+            .iter()
+            .map(|invalid| format!("{}: {}", invalid.field, invalid.message))
+            .collect();
+        write!(f, "invalid settings ({})", fields.join(", "))
+    }
+}
+
+impl std::error::Error for SettingsValidationError {}
+
+pub trait Validate {
+    fn validate(&self) -> Result<(), SettingsValidationError>;
+}
+
+impl Validate for SettingsUserDetails {
+    fn validate(&self) -> Result<(), SettingsValidationError> {
+        let mut validator = Validator::default();
+        validator.range(
+            "next_break_duration_minutes",
+            self.next_break_duration_minutes,
+            BREAK_DURATION_MINUTES,
+        );
+        validator.range("drink_amount_ml", self.drink_amount_ml, DAILY_GOAL_ML);
+        validator.range(
+            "typing_pause_seconds",
+            self.typing_pause_seconds,
+            TYPING_PAUSE_SECONDS,
+        );
+        validator.range(
+            "typing_pause_max_wait_seconds",
+            self.typing_pause_max_wait_seconds,
+            TYPING_PAUSE_MAX_WAIT_SECONDS,
+        );
+        if self.typing_pause_max_wait_seconds < self.typing_pause_seconds {
+            validator.invalid(
+                "typing_pause_max_wait_seconds",
+                "must not be shorter than the typing pause".to_string(),
+            );
+        }
+        validator.range("http_api_port", self.http_api_port, HTTP_API_PORT);
+        validator.range(
+            "escalation_minutes",
+            self.escalation_minutes,
+            ESCALATION_MINUTES,
+        );
+        validator.range("snooze_minutes", self.snooze_minutes, SNOOZE_MINUTES);
+        validator.range("sound_volume", self.sound_volume, SOUND_VOLUME);
+        validator.range(
+            "quiet_hours_start_minute",
+            self.quiet_hours_start_minute,
+            SCHEDULE_MINUTES,
+        );
+        validator.range(
+            "quiet_hours_end_minute",
+            self.quiet_hours_end_minute,
+            SCHEDULE_MINUTES,
+        );
+        if self.mute_during_quiet_hours
+            && self.quiet_hours_start_minute == self.quiet_hours_end_minute
+        {
+            validator.invalid(
+                "quiet_hours_end_minute",
+                "must differ from the start".to_string(),
+            );
+        }
+        validator.range(
+            "overlay_layout.size_percent",
+            self.overlay_layout.size_percent,
+            OVERLAY_SIZE_PERCENT,
+        );
+        validator.range(
+            "overlay_layout.opacity",
+            self.overlay_layout.opacity,
+            OVERLAY_OPACITY,
+        );
+        validator.finish()
+    }
+}
+
+impl Validate for WelcomeUserSettings {
+    fn validate(&self) -> Result<(), SettingsValidationError> {
+        let mut validator = Validator::default();
+        validator.range(
+            "next_break_duration_minutes",
+            self.next_break_duration_minutes,
+            BREAK_DURATION_MINUTES,
+        );
+        validator.range("drink_amount_ml", self.drink_amount_ml, DAILY_GOAL_ML);
+        validator.finish()
+    }
+}
+
+impl Validate for ProfileSchedule {
+    fn validate(&self) -> Result<(), SettingsValidationError> {
+        let mut validator = Validator::default();
+        if self.days.is_empty() {
+            validator.invalid("days", "at least one day is required".to_string());
+        }
+        for day in &self.days {
+            validator.range("days", *day, SCHEDULE_DAYS);
+        }
+        validator.range("start_minute", self.start_minute, SCHEDULE_MINUTES);
+        validator.range("end_minute", self.end_minute, SCHEDULE_MINUTES);
+        if self.start_minute == self.end_minute {
+            validator.invalid("end_minute", "must differ from the start".to_string());
+        }
+        validator.finish()
+    }
+}
+
+/// Checks the sound file only when the user picks another one. A file gone missing later
+/// falls back to the default sound and must not block saving unrelated settings.
+pub fn check_sound_change(
+    previous: Option<&SettingsUserDetails>,
+    user: &SettingsUserDetails,
+) -> Result<(), SettingsValidationError> {
+    let mut validator = Validator::default();
+    if let ReminderSound::File(path) = &user.reminder_sound {
+        if previous.map(|previous| &previous.reminder_sound) != Some(&user.reminder_sound) {
+            if let Err(message) = check_sound_file(Path::new(path)) {
+                validator.invalid("reminder_sound", message);
+            }
+        }
+    }
+    validator.finish()
+}
+
+/// Checks that a sound file exists and holds mp3, ogg or wav audio.
+pub fn check_sound_file(path: &Path) -> Result<(), String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    if !SOUND_FILE_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!(
+            "only {} files are supported",
+            SOUND_FILE_EXTENSIONS.join(", ")
+        ));
+    }
+
+    let mut header = [0u8; 12];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map_err(|err| format!("{} can't be read: {}", path.display(), err))?;
+    let valid = match extension.as_str() {
+        // ID3 tag or the sync bits of an MPEG audio frame
+        "mp3" => header.starts_with(b"ID3") || (header[0] == 0xFF && header[1] & 0xE0 == 0xE0),
+        "ogg" => header.starts_with(b"OggS"),
+        _ => header.starts_with(b"RIFF") && &header[8..12] == b"WAVE",
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("{} is no {} file", path.display(), extension))
+    }
+}
+
+#[derive(Default)]
+struct Validator {
+    invalid_fields: Vec<InvalidField>,
+}
+
+impl Validator {
+    fn range(&mut self, field: &str, value: u32, range: RangeInclusive<u32>) {
+        if !range.contains(&value) {
+            self.invalid(
+                field,
+                format!(
+                    "{} is not between {} and {}",
+                    value,
+                    range.start(),
+                    range.end()
+                ),
+            );
+        }
+    }
+
+    fn invalid(&mut self, field: &str, message: String) {
+        self.invalid_fields.push(InvalidField {
+            field: field.to_string(),
+            message,
+        });
+    }
+
+    fn finish(self) -> Result<(), SettingsValidationError> {
+        if self.invalid_fields.is_empty() {
+            Ok(())
+        } else {
+            Err(SettingsValidationError {
+                invalid_fields: self.invalid_fields,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings_manager::UserSettingsStore;
+
+    #[test]
+    fn test_default_settings_are_valid() {
+        assert_eq!(UserSettingsStore::default().user.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_fields_are_reported() {
+        let mut user = UserSettingsStore::default().user;
+        user.next_break_duration_minutes = 0;
+        user.drink_amount_ml = u32::MAX;
+        user.typing_pause_seconds = 10;
+        user.typing_pause_max_wait_seconds = 5;
+        user.overlay_layout.opacity = 0;
+
+        let fields: Vec<String> = user
+            .validate()
+            .unwrap_err()
+            .invalid_fields
+            .into_iter()
+            .map(|invalid| invalid.field)
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "next_break_duration_minutes",
+                "drink_amount_ml",
+                "typing_pause_max_wait_seconds",
+                "overlay_layout.opacity"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sound_file_is_checked() {
+        let dir = std::env::temp_dir().join(format!("justdrink-sound-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let wav = dir.join("drink.wav");
+        std::fs::write(&wav, b"RIFF\x24\x08\x00\x00WAVEfmt ").unwrap();
+        let fake = dir.join("drink.ogg");
+        std::fs::write(&fake, b"RIFF\x24\x08\x00\x00WAVEfmt ").unwrap();
+
+        assert_eq!(check_sound_file(&wav), Ok(()));
+        assert!(check_sound_file(&fake).is_err());
+        assert!(check_sound_file(&dir.join("missing.mp3")).is_err());
+        assert!(check_sound_file(&dir.join("drink.flac")).is_err());
+
+        let previous = UserSettingsStore::default().user;
+        let mut user = previous.clone();
+        user.reminder_sound = ReminderSound::File(wav.to_string_lossy().to_string());
+        assert_eq!(check_sound_change(Some(&previous), &user), Ok(()));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_missing_sound_file_only_fails_when_chosen() {
+        let mut previous = UserSettingsStore::default().user;
+        previous.reminder_sound = ReminderSound::File("/gone/drink.mp3".to_string());
+        let mut user = previous.clone();
+        user.drink_amount_ml = 2000;
+        assert_eq!(user.validate(), Ok(()));
+        assert_eq!(check_sound_change(Some(&previous), &user), Ok(()));
+
+        let mut chosen = previous.clone();
+        chosen.reminder_sound = ReminderSound::File("/gone/other.mp3".to_string());
+        let fields: Vec<String> = check_sound_change(Some(&previous), &chosen)
+            .unwrap_err()
+            .invalid_fields
+            .into_iter()
+            .map(|invalid| invalid.field)
+            .collect();
+        assert_eq!(fields, vec!["reminder_sound"]);
+    }
+}
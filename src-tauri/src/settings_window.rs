@@ -2,10 +2,10 @@ use crate::alert::Alert;
 use crate::app_config::AppConfig;
 use crate::model::settings::SettingsTabs;
 use crate::settings_manager::UserSettingsStore;
-use crate::{model, CountdownTimerState, SettingsManagerState, TrackingState};
+use crate::settings_validation::{check_sound_change, SettingsValidationError, Validate};
+use crate::{model, SettingsManagerState, TrackingState};
 use log::info;
 use std::string::ToString;
-use std::time::Duration;
 use tauri::{AppHandle, Manager, Runtime, State, Window};
 
 pub(crate) const WINDOW_LABEL: &'static str = "settings";
@@ -35,6 +35,9 @@ pub async fn load_settings(
     let version = app.app_handle().config().version.clone();
     info!("load settings data - done");
 
+    let policy = settings.policy();
+    let locked_fields = policy.locked_fields();
+    let min_break_duration_minutes = policy.min_break_duration_minutes();
     let settings = settings
         .get_settings()
         .unwrap_or(UserSettingsStore::default());
@@ -47,6 +50,8 @@ pub async fn load_settings(
         },
         user: settings.user,
         selected_tab: SettingsTabs::Session,
+        locked_fields,
+        min_break_duration_minutes,
     })
 }
 
@@ -62,15 +67,13 @@ pub fn update_settings(
     app_handle: AppHandle,
     settings: model::settings::SettingsUserDetails,
     settings_manager: State<SettingsManagerState>,
-    timer: State<CountdownTimerState>,
-) -> () {
-    if settings.active {
-        timer.start(Duration::from_secs(
-            (settings.next_break_duration_minutes * 60).into(),
-        ));
-    } else {
-        timer.stop();
-    }
+) -> Result<(), SettingsValidationError> {
+    settings.validate()?;
+    check_sound_change(
+        settings_manager.get_settings().map(|s| s.user).as_ref(),
+        &settings,
+    )?;
+    settings_manager.policy().check(&settings)?;
 
     settings_manager
         .update_user(settings)
@@ -83,6 +86,7 @@ pub fn update_settings(
             );
             ()
         });
+    Ok(())
 }
 
 #[specta::specta]
@@ -2,10 +2,12 @@ use std::error::Error;
 use std::sync::Mutex;
 use std::time::Duration;
 use log::{info, warn};
-use tauri::{App, Manager};
+use tauri::{App, AppHandle, Manager};
 use tauri_plugin_aptabase::EventTracker;
-use tauri_specta::Builder;
-use crate::{dashboard_window, detect_idling, feedback_window, license_manager, model, session_window, settings_system, show_dashboard, subscription_manager, tray, updater_window, welcome_window, CountdownTimerState, FeedbackSenderState, LicenseManagerState, SettingsManagerState, SettingsSystemState, SubscriptionManagerState, TrackingState};
+use tauri_specta::{Builder, Event};
+use crate::{cli, dashboard_window, detect_idling, escalation, feedback_window, http_api, instance_control, license_manager, model, overlay_monitors, power, session_window, settings_profiles, settings_system, show_dashboard, subscription_manager, tray, updater_window, welcome_window, CliState, CountdownTimerState, EscalationState, FeedbackSenderState, HttpApiState, IdleMonitorState, LicenseManagerState, PowerMonitorState, ProfileSchedulerState, SettingsManagerState, SettingsSystemState, SubscriptionManagerState, TrackingState};
+use crate::alert::Alert;
+use crate::cli::Cli;
 use crate::countdown_timer::CountdownTimer;
 use crate::model::settings::WelcomeWizardMode;
 use crate::settings_manager::SettingsManager;
@@ -15,7 +17,12 @@ use crate::tracking::Tracking;
 use tauri::ActivationPolicy;
 
 
-pub fn setup(builder: Builder, app: &mut App) -> Result<(), Box<dyn Error>> {
+pub fn setup(builder: Builder, cli: Cli, app: &mut App) -> Result<(), Box<dyn Error>> {
+    if let (Some(_), Some(reply_to)) = (&cli.command, &cli.reply_to) {
+        warn!("command for a running instance, but Just Drink! is not running");
+        instance_control::reply_not_running(reply_to);
+        std::process::exit(1);
+    }
     app.track_event("app_started", None);
     builder.mount_events(app.app_handle());
     let device_id = model::device::DeviceId::lookup()?;
@@ -30,7 +37,15 @@ pub fn setup(builder: Builder, app: &mut App) -> Result<(), Box<dyn Error>> {
         device_id.clone(),
     ));
 
-    let settings_manager = SettingsManager::new(app.app_handle())?;
+    let overrides = cli.settings_overrides();
+    app.manage::<CliState>(cli);
+    let profile = overrides.profile.clone();
+    let settings_manager = SettingsManager::new(app.app_handle(), overrides)?;
+    if let (Some(profile), Some(settings)) = (profile, settings_manager.get_stored_settings()) {
+        if !settings.profile_names().contains(&profile) {
+            warn!("unknown profile '{profile}' on command line, use the active profile");
+        }
+    }
 
     app.manage::<CountdownTimerState>(CountdownTimer::new(app.app_handle()));
     app.manage::<SettingsManagerState>(settings_manager);
@@ -38,18 +53,39 @@ pub fn setup(builder: Builder, app: &mut App) -> Result<(), Box<dyn Error>> {
     app.manage::<SettingsSystemState>(Mutex::new(settings_system::SettingsSystem::load(
         app.app_handle(),
     )));
+    subscribe_settings_changes(app.app_handle());
+    if app.state::<SettingsManagerState>().is_read_only() {
+        app.app_handle().alert(
+            "Settings are read-only",
+            "Your settings were saved by a newer version of Just Drink!. Changes are not saved until you update.",
+            None,
+            false,
+        );
+    }
 
-    tray::create_tray(app.handle())?;
+    // headless runs the timer and the control interfaces only, reminders become notifications
+    let headless = app.state::<CliState>().headless;
+    if !headless {
+        tray::create_tray(app.handle())?;
+    }
 
-    match app.state::<SettingsManagerState>().get_settings() {
+    let mut settings = app.state::<SettingsManagerState>().get_settings();
+    if settings.is_none() && headless {
+        warn!("settings are missing, run headless with the default settings");
+        app.state::<SettingsManagerState>().use_defaults()?;
+        settings = app.state::<SettingsManagerState>().get_settings();
+    }
+    match settings {
         Some(settings) => {
-            tray::show_tray_icon(app.app_handle());
-            if dashboard_window::should_show_dashboard() {
+            if !headless {
+                tray::show_tray_icon(app.app_handle());
+            }
+            if dashboard_window::should_show_dashboard(&app.state::<CliState>()) {
                 show_dashboard(app.app_handle());
             }
             app.state::<CountdownTimerState>()
                 .start(Duration::from_secs(
-                    (settings.user.next_break_duration_minutes * 60) as u64,
+                    u64::from(settings.user.next_break_duration_minutes) * 60,
                 ));
             #[cfg(target_os = "macos")]
             app.app_handle()
@@ -65,9 +101,27 @@ pub fn setup(builder: Builder, app: &mut App) -> Result<(), Box<dyn Error>> {
         }
     }
 
+    app.manage::<EscalationState>(escalation::Escalation::new(app.app_handle()));
     session_window::init(app.app_handle())?;
-    detect_idling::init(app.app_handle())?;
+    app.manage::<IdleMonitorState>(detect_idling::IdleMonitor::start(app.app_handle())?);
+    app.manage::<PowerMonitorState>(power::PowerMonitor::start(app.app_handle())?);
+    app.manage::<ProfileSchedulerState>(settings_profiles::ProfileScheduler::start(
+        app.app_handle(),
+    )?);
+    app.manage::<HttpApiState>(http_api::HttpApi::new(app.app_handle()));
+    if let Some(settings) = app.state::<SettingsManagerState>().get_settings() {
+        http_api::init(app.app_handle(), &settings.user);
+    }
+    #[cfg(unix)]
+    crate::control_socket::init(app.app_handle())
+        .unwrap_or_else(|err| warn!("control socket not available: {:?}", err));
+    #[cfg(target_os = "linux")]
+    crate::dbus_service::init(app.app_handle())
+        .unwrap_or_else(|err| warn!("d-bus service not available: {:?}", err));
 
+    if headless {
+        return Ok(());
+    }
     let app_handle = app.handle().clone();
     tauri::async_runtime::spawn(async move {
         info!("show updater window");
@@ -77,4 +131,33 @@ pub fn setup(builder: Builder, app: &mut App) -> Result<(), Box<dyn Error>> {
     });
 
     Ok(())
-}
\ No newline at end of file
+}
+
+fn subscribe_settings_changes(app: &AppHandle) {
+    let app_handle = app.clone();
+    app.state::<SettingsManagerState>().subscribe(move |event| {
+        event
+            .emit(&app_handle)
+            .unwrap_or_else(|err| warn!("unable to send settings change to windows: {err}"));
+
+        if event.has_changed("active") || event.has_changed("next_break_duration_minutes") {
+            let timer = app_handle.state::<CountdownTimerState>();
+            if event.user.active {
+                timer.start(Duration::from_secs(
+                    u64::from(event.user.next_break_duration_minutes) * 60,
+                ));
+            } else {
+                timer.stop();
+            }
+        }
+        if event.has_changed("http_api_enabled") || event.has_changed("http_api_port") {
+            http_api::init(&app_handle, &event.user);
+        }
+        if (event.has_changed("overlay_monitor") || event.has_changed("overlay_layout"))
+            && !cli::is_headless(&app_handle)
+        {
+            overlay_monitors::place(&app_handle)
+                .unwrap_or_else(|err| warn!("unable to place session windows: {err}"));
+        }
+    });
+}
@@ -0,0 +1,231 @@
+#[cfg(unix)]
+use crate::control_socket;
+use crate::instance_control::{InstanceStatus, StatusFormat};
+#[cfg(unix)]
+use anyhow::anyhow;
+use serde_json::json;
+#[cfg(unix)]
+use serde_json::Value;
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+#[cfg(unix)]
+const STATUS_ID: u64 = 1;
+
+/// Status line for panels, `--follow` keeps printing one line per change.
+///
+/// waybar: `"exec": "justdrink status --format waybar --follow", "return-type": "json"`,
+/// with `justdrink toggle` and `justdrink drink-now` as click commands.
+/// i3blocks: `command=justdrink status --format i3blocks`, left click toggles the reminders,
+/// right click starts a reminder now.
+pub fn format_status(status: &InstanceStatus, format: StatusFormat, follow: bool) -> String {
+    let title = status.timer.to_title().unwrap_or_else(|| "Off".to_string());
+    let progress = format!("{}/{} ml", status.intake_ml, status.goal_ml);
+    let percentage = (status.intake_ml * 100)
+        .checked_div(status.goal_ml)
+        .unwrap_or_default()
+        .min(100);
+
+    match format {
+        StatusFormat::Text => status.to_text(),
+        StatusFormat::Waybar => json!({
+            "text": title,
+            "alt": status.timer.state_name(),
+            "class": status.timer.state_name(),
+            "tooltip": format!("{}\n{} today", status.timer.to_text(), progress),
+            "percentage": percentage,
+        })
+        .to_string(),
+        // i3blocks reads full text and short text from separate lines, a persistent block
+        // takes every line as new full text
+        StatusFormat::I3blocks if follow => format!("{} {}", title, progress),
+        StatusFormat::I3blocks => format!("{} {}\n{}", title, progress, title),
+        StatusFormat::Json => json!({
+            "state": status.timer.state_name(),
+            "title": title,
+            "text": status.timer.to_text(),
+            "remaining_seconds": status.timer.remaining_seconds(),
+            "pause_reason": status.timer.pause_reason(),
+            "intake_ml": status.intake_ml,
+            "goal_ml": status.goal_ml,
+            "active_profile": status.active_profile,
+        })
+        .to_string(),
+    }
+}
+
+/// Method of the control socket for a mouse button of i3blocks.
+#[cfg(unix)]
+fn click_method(button: &str) -> Option<&'static str> {
+    match button.trim() {
+        "1" => Some("toggle"),
+        "3" => Some("start_session"),
+        _ => None,
+    }
+}
+
+#[cfg(unix)]
+pub fn run(format: StatusFormat, follow: bool) -> i32 {
+    match follow_status(format, follow) {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("Unable to reach Just Drink!: {}", err);
+            1
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn run(_format: StatusFormat, _follow: bool) -> i32 {
+    eprintln!("Status formats are available on Linux and macOS only");
+    1
+}
+
+#[cfg(unix)]
+fn follow_status(format: StatusFormat, follow: bool) -> Result<(), anyhow::Error> {
+    let stream = UnixStream::connect(control_socket::socket_path())?;
+    let mut writer = stream.try_clone()?;
+    let reader = BufReader::new(stream);
+
+    // i3blocks runs the command again after a click
+    if let Some(method) = std::env::var("BLOCK_BUTTON")
+        .ok()
+        .and_then(|button| click_method(&button))
+    {
+        request(&mut writer, 0, method)?;
+    }
+    request(
+        &mut writer,
+        STATUS_ID,
+        if follow { "subscribe" } else { "status" },
+    )?;
+
+    // a persistent i3blocks block gets the clicks on stdin, as button or as JSON object
+    if follow && format == StatusFormat::I3blocks {
+        let mut click_writer = writer.try_clone()?;
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lines().map_while(Result::ok) {
+                let button = serde_json::from_str::<Value>(&line)
+                    .ok()
+                    .and_then(|click| click.get("button").map(Value::to_string))
+                    .unwrap_or(line);
+                if let Some(method) = click_method(&button) {
+                    if request(&mut click_writer, 0, method).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    let mut status: Option<InstanceStatus> = None;
+    let mut printed = String::new();
+    for line in reader.lines() {
+        let message: Value = serde_json::from_str(&line?)?;
+        if let Some(error) = message.get("error") {
+            return Err(anyhow!("{}", error["message"]));
+        }
+        match (
+            message.get("method").and_then(Value::as_str),
+            status.as_mut(),
+        ) {
+            (Some("countdown"), Some(status)) => {
+                status.timer = serde_json::from_value(message["params"]["status"].clone())?;
+            }
+            (Some("intake"), Some(status)) => {
+                status.intake_ml = serde_json::from_value(message["params"]["intake_ml"].clone())?;
+                status.goal_ml = serde_json::from_value(message["params"]["goal_ml"].clone())?;
+            }
+            (Some(_), _) => continue,
+            (None, _) => {
+                status = Some(serde_json::from_value(message["result"].clone())?);
+                if message["id"] != json!(STATUS_ID) && !follow {
+                    continue;
+                }
+            }
+        }
+
+        let Some(status) = &status else {
+            continue;
+        };
+        let output = format_status(status, format, follow);
+        if output != printed {
+            println!("{}", output);
+            printed = output;
+        }
+        if !follow {
+            return Ok(());
+        }
+    }
+    Err(anyhow!("Just Drink! has been closed"))
+}
+
+#[cfg(unix)]
+fn request(writer: &mut impl Write, id: u64, method: &str) -> std::io::Result<()> {
+    let line = json!({ "jsonrpc": "2.0", "id": id, "method": method });
+    writeln!(writer, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::countdown_timer::{PauseOrigin, TimerStatus};
+
+    fn status(timer: TimerStatus) -> InstanceStatus {
+        InstanceStatus {
+            timer,
+            active_profile: Some("Default".to_string()),
+            intake_ml: 750,
+            goal_ml: 2000,
+        }
+    }
+
+    #[test]
+    fn test_waybar() {
+        let output = format_status(
+            &status(TimerStatus::Active(750)),
+            StatusFormat::Waybar,
+            true,
+        );
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["text"], "12m");
+        assert_eq!(output["class"], "active");
+        assert_eq!(output["percentage"], 37);
+    }
+
+    #[test]
+    fn test_i3blocks() {
+        let paused = status(TimerStatus::Paused(PauseOrigin::User, 300));
+        assert_eq!(
+            format_status(&paused, StatusFormat::I3blocks, false),
+            "Silent 750/2000 ml\nSilent"
+        );
+        assert_eq!(
+            format_status(&paused, StatusFormat::I3blocks, true),
+            "Silent 750/2000 ml"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let output = format_status(
+            &status(TimerStatus::Paused(PauseOrigin::Idle, 300)),
+            StatusFormat::Json,
+            false,
+        );
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["state"], "paused");
+        assert_eq!(output["pause_reason"], "idle");
+        assert_eq!(output["remaining_seconds"], 300);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_click_method() {
+        assert_eq!(click_method("1"), Some("toggle"));
+        assert_eq!(click_method("3\n"), Some("start_session"));
+        assert_eq!(click_method("2"), None);
+    }
+}
@@ -1,12 +1,10 @@
 use crate::alert::Alert;
 use crate::countdown_timer::{CountdownEvent, CountdownTimer, PauseOrigin, TimerStatus};
 use crate::model::settings::SettingsTabs;
-use crate::pretty_time::PrettyTime;
-use crate::{dashboard_window, feedback_window, session_window, settings_window, updater_window, CountdownTimerState};
+use crate::{dashboard_window, feedback_window, session_window, settings_window, updater_window, CountdownTimerState, SettingsManagerState};
 use anyhow::anyhow;
-use std::time::Duration;
 use tauri::image::Image;
-use tauri::menu::{IconMenuItem, PredefinedMenuItem, Submenu};
+use tauri::menu::{CheckMenuItem, IconMenuItem, PredefinedMenuItem, Submenu};
 use tauri::path::BaseDirectory;
 use tauri::{
     menu::{Menu, MenuItem},
@@ -16,6 +14,7 @@ use tauri::{
 use tauri_specta::Event;
 
 const TRAY_ID: &'static str = "tray";
+const PROFILE_MENU_PREFIX: &str = "profile:";
 
 pub fn create_tray(main_app: &AppHandle<Wry>) -> tauri::Result<()> {
     let menu_status = MenuItem::with_id(main_app, "dashboard", "Dashboard", true, None::<&str>)?;
@@ -27,6 +26,17 @@ pub fn create_tray(main_app: &AppHandle<Wry>) -> tauri::Result<()> {
         None::<&str>,
     )?;
 
+    let menu_profiles = Submenu::new(main_app, "Profile", true)?;
+    match main_app.state::<SettingsManagerState>().get_settings() {
+        Some(settings) => update_profile_menu(
+            main_app,
+            &menu_profiles,
+            &settings.active_profile,
+            &settings.profile_names(),
+        )?,
+        None => menu_profiles.set_enabled(false)?,
+    }
+
     let menu = Menu::with_items(
         main_app,
         &[
@@ -41,6 +51,7 @@ pub fn create_tray(main_app: &AppHandle<Wry>) -> tauri::Result<()> {
                     &menu_timer_control,
                 ],
             )?,
+            &menu_profiles,
             &IconMenuItem::with_id(
                 main_app,
                 "settings",
@@ -73,6 +84,7 @@ pub fn create_tray(main_app: &AppHandle<Wry>) -> tauri::Result<()> {
         ],
     )?;
 
+    let menu_profiles_event = menu_profiles.clone();
     let tray_icon = tray_icon(main_app.app_handle())?;
     let tray = TrayIconBuilder::with_id(TRAY_ID)
         .icon(tray_icon)
@@ -96,7 +108,7 @@ pub fn create_tray(main_app: &AppHandle<Wry>) -> tauri::Result<()> {
                     let timer = app_handle.app_handle().state::<CountdownTimerState>();
                     timer.restart();
 
-                    session_window::show_session(app_handle.app_handle(), None).await.unwrap_or_else(|e| {
+                    session_window::show_session(app_handle.app_handle(), None, false).await.unwrap_or_else(|e| {
                         app_handle.alert(
                             "Error while starting the session",
                             "I am sorry, we are unable to start the session.",
@@ -158,12 +170,63 @@ pub fn create_tray(main_app: &AppHandle<Wry>) -> tauri::Result<()> {
             "quit" => {
                 app.exit(0);
             }
+            id if id.starts_with(PROFILE_MENU_PREFIX) => {
+                let settings_manager = app.state::<SettingsManagerState>();
+                settings_manager
+                    .switch_profile(&id[PROFILE_MENU_PREFIX.len()..])
+                    .unwrap_or_else(|e| {
+                        app.alert(
+                            "Error while switching profile",
+                            "I am sorry, we are unable to switch the profile.",
+                            Some(e),
+                            false,
+                        );
+                    });
+                // clicking toggles the check mark, even if the profile was already active
+                if let Some(settings) = settings_manager.get_settings() {
+                    update_profile_menu(
+                        app,
+                        &menu_profiles_event,
+                        &settings.active_profile,
+                        &settings.profile_names(),
+                    )
+                    .map_err(|e| log::error!("Failed to update profile menu: {}", e))
+                    .ok();
+                }
+            }
             _ => {}
         })
         .build(main_app)?;
 
     tray.set_visible(false)?;
 
+    let app_handle_settings = main_app.clone();
+    let menu_status_settings = menu_status.clone();
+    main_app
+        .state::<SettingsManagerState>()
+        .subscribe(move |event| {
+            // a stopped timer doesn't send any countdown events, reset the tray here
+            if event.has_changed("active") && !event.user.active {
+                menu_status_settings
+                    .set_text("Dashboard (Not running)")
+                    .map_err(|e| log::error!("Failed to update tray menu: {}", e))
+                    .ok();
+                update_tray_title(&app_handle_settings, TimerStatus::NotStarted(0))
+                    .map_err(|e| log::error!("Failed to update tray title: {}", e))
+                    .ok();
+            }
+            if event.has_changed("active_profile") || event.has_changed("profiles") {
+                update_profile_menu(
+                    &app_handle_settings,
+                    &menu_profiles,
+                    &event.active_profile,
+                    &event.profile_names,
+                )
+                .map_err(|e| log::error!("Failed to update profile menu: {}", e))
+                .ok();
+            }
+        });
+
     let app_handle = main_app.clone();
     CountdownEvent::listen(main_app.app_handle(), move |event| {
         let timer_control_text = if event.payload.status.is_running() {
@@ -207,21 +270,29 @@ pub fn show_tray_icon(app: &AppHandle) -> () {
         .expect("should be able to access the tray icon")
 }
 
+fn update_profile_menu(
+    app_handle: &AppHandle<Wry>,
+    menu: &Submenu<Wry>,
+    active_profile: &str,
+    profile_names: &[String],
+) -> tauri::Result<()> {
+    while menu.remove_at(0)?.is_some() {}
+    for name in profile_names {
+        menu.append(&CheckMenuItem::with_id(
+            app_handle,
+            format!("{PROFILE_MENU_PREFIX}{name}"),
+            name,
+            true,
+            name == active_profile,
+            None::<&str>,
+        )?)?;
+    }
+    menu.set_enabled(!profile_names.is_empty())
+}
+
 pub fn update_tray_title(app_handle: &AppHandle<Wry>, status: TimerStatus) -> tauri::Result<()> {
     if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
-        let tray_text = match status {
-            TimerStatus::NotStarted(_) => None,
-            TimerStatus::Active(duration) => {
-                Some(Duration::from_secs(duration as u64).to_pretty_time())
-            }
-            TimerStatus::Paused(origin, _) => match origin {
-                PauseOrigin::Idle => Some("Idle".to_string()),
-                PauseOrigin::PreventSleep(_) => Some("Busy".to_string()),
-                PauseOrigin::User => Some("Silent".to_string()),
-            },
-            TimerStatus::Finished => None,
-        };
-        tray.set_title(tray_text)?;
+        tray.set_title(status.to_title())?;
     }
     Ok(())
 }
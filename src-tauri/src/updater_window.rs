@@ -82,7 +82,7 @@ fn check_for_new_version_required<R: Runtime>(
     };
 
     if check_for_updates {
-        settings_system.set_last_check_date(app_handle)?;
+        settings_system.set_last_check_date()?;
     }
 
     Ok(check_for_updates)
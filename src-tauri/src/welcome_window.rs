@@ -4,14 +4,14 @@ use crate::model::device::DeviceId;
 use crate::model::settings::{SettingsUserDetails, WelcomeWizardMode};
 use crate::model::welcome::{WelcomeLoadSettings, WelcomeUserSettings};
 use crate::settings_manager::{SettingsManager, UserSettingsStore};
+use crate::settings_validation::{SettingsValidationError, Validate};
 use crate::tracking::Event;
 use crate::{
-    dashboard_window, tracking, tray, welcome_window, CountdownTimerState, LicenseManagerState,
-    SettingsManagerState, SubscriptionManagerState, TrackingState,
+    dashboard_window, tracking, tray, welcome_window, LicenseManagerState, SettingsManagerState,
+    SubscriptionManagerState, TrackingState,
 };
 use anyhow::anyhow;
-use log::{info, warn};
-use std::time::Duration;
+use log::info;
 #[cfg(target_os = "macos")]
 use tauri::ActivationPolicy;
 use tauri::{AppHandle, Manager, State};
@@ -128,8 +128,9 @@ pub async fn welcome_save(
     settings: WelcomeUserSettings,
     settings_manager: State<'_, SettingsManagerState>,
     subscription_manager: State<'_, SubscriptionManagerState>,
-    timer: State<'_, CountdownTimerState>,
-) -> Result<(), String> {
+) -> Result<(), SettingsValidationError> {
+    settings.validate()?;
+
     tray::show_tray_icon(app.app_handle());
 
     if let Some(consent) = consent {
@@ -168,16 +169,6 @@ pub async fn welcome_save(
             false)
     );
 
-    match settings_manager.get_settings() {
-        None => {
-            warn!("no settings saved, can't start timer")
-        }
-        Some(s) => {
-            timer.start(Duration::from_secs(
-                (s.user.next_break_duration_minutes * 60) as u64,
-            ));
-        }
-    }
     Ok(())
 }
 
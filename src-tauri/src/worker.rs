@@ -0,0 +1,84 @@
+use log::{debug, info, warn};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Background thread repeating a check at a fixed interval until it is stopped.
+///
+/// The thread waits on a channel instead of sleeping, so stopping it on shutdown takes
+/// effect immediately.
+pub struct Worker {
+    name: String,
+    sender: Mutex<Option<Sender<()>>>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Worker {
+    /// Runs `check` right away and then after every `interval`.
+    pub fn start(
+        name: &str,
+        interval: Duration,
+        mut check: impl FnMut() + Send + 'static,
+    ) -> Result<Self, anyhow::Error> {
+        let (sender, receiver) = channel::<()>();
+        let thread_name = name.to_string();
+        let handle = std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                loop {
+                    check();
+                    match receiver.recv_timeout(interval) {
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                info!("{thread_name} stopped");
+            })?;
+
+        Ok(Worker {
+            name: name.to_string(),
+            sender: Mutex::new(Some(sender)),
+            handle: Mutex::new(Some(handle)),
+        })
+    }
+
+    /// Stops the worker and waits for the background thread to finish.
+    pub fn stop(&self) {
+        if let Some(sender) = self.sender.lock().unwrap().take() {
+            sender
+                .send(())
+                .unwrap_or_else(|_| debug!("{} already stopped", self.name));
+        }
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle
+                .join()
+                .unwrap_or_else(|_| warn!("{} thread panicked", self.name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    #[test]
+    fn test_stop_ends_the_wait() {
+        let checks = Arc::new(AtomicU32::new(0));
+        let counter = checks.clone();
+        let worker = Worker::start("test-worker", Duration::from_secs(60), move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+
+        let started = Instant::now();
+        std::thread::sleep(Duration::from_millis(50));
+        worker.stop();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(checks.load(Ordering::SeqCst), 1);
+        worker.stop();
+    }
+}
@@ -29,8 +29,18 @@ async openAppStoreFeedback() : Promise<null> {
 async startSession(drinkSettings: SessionStartEvent | null) : Promise<null> {
     return await TAURI_INVOKE("start_session", { drinkSettings });
 },
-async endSession(demoMode: boolean) : Promise<null> {
-    return await TAURI_INVOKE("end_session", { demoMode });
+/**
+ * Ends the session, `outcome` is the answer of an interactive overlay. An overlay
+ * nobody answered ends without one.
+ */
+async endSession(demoMode: boolean, outcome: SessionOutcome | null) : Promise<null> {
+    return await TAURI_INVOKE("end_session", { demoMode, outcome });
+},
+/**
+ * Plays the sound in a demo session, after checking a sound file of the user.
+ */
+async previewSound(sound: ReminderSound, volume: number) : Promise<null> {
+    return await TAURI_INVOKE("preview_sound", { sound, volume });
 },
 async openSettings() : Promise<null> {
     return await TAURI_INVOKE("open_settings");
@@ -47,6 +57,36 @@ async updateSettings(settings: SettingsUserDetails) : Promise<null> {
 async openBrowser(url: string, close: boolean) : Promise<null> {
     return await TAURI_INVOKE("open_browser", { url, close });
 },
+async settingsExport(path: string) : Promise<null> {
+    return await TAURI_INVOKE("settings_export", { path });
+},
+async settingsImportPreview(path: string) : Promise<SettingsImportPreview> {
+    return await TAURI_INVOKE("settings_import_preview", { path });
+},
+async settingsImport(path: string) : Promise<null> {
+    return await TAURI_INVOKE("settings_import", { path });
+},
+async loadProfiles() : Promise<SettingsProfiles> {
+    return await TAURI_INVOKE("load_profiles");
+},
+async switchProfile(name: string) : Promise<null> {
+    return await TAURI_INVOKE("switch_profile", { name });
+},
+async saveProfile(name: string, schedule: ProfileSchedule | null) : Promise<null> {
+    return await TAURI_INVOKE("save_profile", { name, schedule });
+},
+async deleteProfile(name: string) : Promise<null> {
+    return await TAURI_INVOKE("delete_profile", { name });
+},
+async httpApiToken() : Promise<string> {
+    return await TAURI_INVOKE("http_api_token");
+},
+/**
+ * Creates a new token, clients with the old token are rejected from now on.
+ */
+async httpApiRegenerateToken() : Promise<string> {
+    return await TAURI_INVOKE("http_api_regenerate_token");
+},
 async welcomeWith(welcomeWizardMode: WelcomeWizardMode) : Promise<void> {
     await TAURI_INVOKE("welcome_with", { welcomeWizardMode });
 },
@@ -81,17 +121,21 @@ async requestLicenseStatus() : Promise<LicenseData> {
 
 export const events = __makeEvents__<{
 countdownEvent: CountdownEvent,
+intakeEvent: IntakeEvent,
 licenseResult: LicenseResult,
 sessionStartEvent: SessionStartEvent,
 settings: Settings,
+settingsChangedEvent: SettingsChangedEvent,
 settingsUserDetails: SettingsUserDetails,
 timerStatus: TimerStatus,
 welcomeWizardMode: WelcomeWizardMode
 }>({
 countdownEvent: "countdown-event",
+intakeEvent: "intake-event",
 licenseResult: "license-result",
 sessionStartEvent: "session-start-event",
 settings: "settings",
+settingsChangedEvent: "settings-changed-event",
 settingsUserDetails: "settings-user-details",
 timerStatus: "timer-status",
 welcomeWizardMode: "welcome-wizard-mode"
@@ -109,6 +153,11 @@ export type CountdownEvent = { status: TimerStatus }
 export type DrinkCharacter = "YoungWoman" | "YoungMan"
 export type FeedbackRate = "UNKNOWN" | "BAD" | "OK" | "AWESOME"
 export type GenderType = "Male" | "Female" | "Other"
+/**
+ * Water logged today, sent whenever a drink is logged.
+ */
+export type IntakeEvent = { intake_ml: number; goal_ml: number }
+export type InvalidField = { field: string; message: string }
 export type LicenseData = { payment: LicensePaymentInfo; info: LicenseInfo }
 export type LicenseInfo = { status: LicenseInfoStatus; license_key: string | null; message: string | null }
 export type LicenseInfoStatus = "Trial" | "Paid" | "Full" | "Invalid"
@@ -116,11 +165,144 @@ export type LicensePaymentInfo = { total_trail_days: number; trial_days_left: nu
 export type LicensePaymentStatus = "GoToCheckout" | "ReadyToCapture" | "Paid" | "Canceled" | "Error"
 export type LicenseResult = { status: LicenseResultStatus; error: string | null }
 export type LicenseResultStatus = "Success" | "Error"
+/**
+ * Layout of the reminder overlay, the backend sizes the window and the page draws it.
+ */
+export type OverlayLayout = { placement: OverlayPlacement; 
+/**
+ * Width of the corner widget in percent of the monitor width.
+ */
+size_percent: number; 
+/**
+ * Opacity of the overlay in percent.
+ */
+opacity: number; 
+/**
+ * Clicks and keys confirm the reminder instead of passing through to the windows below.
+ */
+interactive: boolean }
+/**
+ * Monitors showing the reminder overlay.
+ */
+export type OverlayMonitor = 
+/**
+ * Primary monitor of the system
+ */
+"Primary" | 
+/**
+ * Monitor with the mouse cursor when the reminder starts
+ */
+"Cursor" | 
+/**
+ * Every connected monitor, the sound plays once
+ */
+"All"
+/**
+ * Placement of the reminder overlay on its monitor.
+ */
+export type OverlayPlacement = 
+/**
+ * Covers the whole monitor
+ */
+"FullScreen" | 
+/**
+ * Widget in the bottom right corner
+ */
+"Corner"
 export type PauseOrigin = "Idle" | { PreventSleep: string } | "User"
-export type SessionStartEvent = { selected_drink_character: DrinkCharacter; sip_size: SipSize; demo_mode: boolean }
-export type Settings = { app: AppDetails; user: SettingsUserDetails; selected_tab: SettingsTabs }
+/**
+ * Local time window in which a profile is selected automatically.
+ * Days are numbered from 1 (Monday) to 7 (Sunday), times are minutes since midnight.
+ * A window ending before it starts spans midnight.
+ */
+export type ProfileSchedule = { days: number[]; start_minute: number; end_minute: number }
+/**
+ * Sound of the reminder overlay.
+ */
+export type ReminderSound = 
+/**
+ * Gulps of the drink character
+ */
+"Character" | 
+/**
+ * Sparkling water only
+ */
+"Sparkling" | 
+/**
+ * Audio file of the user, mp3, ogg or wav
+ */
+{ File: string }
+/**
+ * How a drink reminder is presented.
+ */
+export type ReminderStyle = 
+/**
+ * Full screen video overlay
+ */
+"Overlay" | 
+/**
+ * Native desktop notification with action buttons
+ */
+"Notification"
+/**
+ * Answer of the user to a drink reminder.
+ */
+export type SessionOutcome = "Drank" | "Snooze" | "Skip"
+export type SessionStartEvent = { selected_drink_character: DrinkCharacter; sip_size: SipSize; demo_mode: boolean; 
+/**
+ * Overlay without sound, the first overlay of an escalating reminder or during
+ * quiet hours.
+ */
+muted: boolean; sound: ReminderSound; 
+/**
+ * Volume in percent.
+ */
+volume: number; layout: OverlayLayout }
+export type Settings = { app: AppDetails; user: SettingsUserDetails; selected_tab: SettingsTabs; 
+/**
+ * Fields enforced by the administrator policy, read-only for the user.
+ */
+locked_fields: string[]; min_break_duration_minutes: number }
+export type SettingsChange = { field: string; current: string | null; 
+/**
+ * Missing if the import removes the profile.
+ */
+imported: string | null }
+export type SettingsChangedEvent = { changed_fields: string[]; user: SettingsUserDetails; active_profile: string; profile_names: string[] }
+export type SettingsImportPreview = { exported_by_version: string; exported_at: string; changes: SettingsChange[] }
+/**
+ * Named set of user settings, only one profile is active at a time.
+ */
+export type SettingsProfile = { name: string; user: SettingsUserDetails; schedule: ProfileSchedule | null }
+export type SettingsProfiles = { active_profile: string; profiles: SettingsProfile[] }
 export type SettingsTabs = "Session" | "Tracking" | "License" | "About"
-export type SettingsUserDetails = { next_break_duration_minutes: number; drink_amount_ml: number; sip_size: SipSize; character: DrinkCharacter; gender_type: GenderType; consent: boolean; active: boolean; allow_tracking: boolean; enable_on_startup: boolean; beta_version: boolean; enable_idle_detection: boolean }
+export type SettingsUserDetails = { next_break_duration_minutes: number; drink_amount_ml: number; sip_size: SipSize; character: DrinkCharacter; gender_type: GenderType; consent: boolean; active: boolean; allow_tracking: boolean; enable_on_startup: boolean; beta_version: boolean; enable_idle_detection: boolean; 
+/**
+ * Pauses the timer while the camera or microphone is in use.
+ */
+pause_during_calls: boolean; wait_for_typing_pause: boolean; typing_pause_seconds: number; typing_pause_max_wait_seconds: number; notification_on_battery: boolean; reduce_work_on_low_battery: boolean; 
+/**
+ * Local HTTP interface for integrations, protected by a bearer token.
+ */
+http_api_enabled: boolean; http_api_port: number; reminder_style: ReminderStyle; 
+/**
+ * Follow up an unanswered reminder with the overlay, then with the overlay and sound.
+ */
+escalate_reminders: boolean; escalation_minutes: number; 
+/**
+ * Minutes until the next reminder after "Snooze".
+ */
+snooze_minutes: number; reminder_sound: ReminderSound; 
+/**
+ * Volume of the reminder sound in percent.
+ */
+sound_volume: number; 
+/**
+ * Mute the reminder sound between the start and end of the quiet hours, given in
+ * minutes since midnight. Quiet hours ending before they start span midnight.
+ */
+mute_during_quiet_hours: boolean; quiet_hours_start_minute: number; quiet_hours_end_minute: number; overlay_monitor: OverlayMonitor; overlay_layout: OverlayLayout }
+export type SettingsValidationError = { invalid_fields: InvalidField[] }
 export type SipSize = "BigSip" | "HalfCup" | "FullCup"
 export type TimerStatus = { NotStarted: number } | { Active: number } | { Paused: [PauseOrigin, number] } | "Finished"
 export type WelcomeLoadSettings = { user: SettingsUserDetails | null; device_id: string; backend_url: string }